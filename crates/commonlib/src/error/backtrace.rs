@@ -0,0 +1,133 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use std::backtrace::{Backtrace, BacktraceStatus};
+
+// The frames of the runtime and of the errors themselves, which say nothing about the failure
+const IGNORED_PREFIXES: [&str; 3] = ["std::", "core::", "alloc::"];
+const IGNORED_FUNCTIONS: [&str; 5] = [
+  "main",
+  "<unknown>",
+  "_start",
+  "__libc_start_main",
+  "__libc_start_call_main",
+];
+const ERROR_MODULE: &str = "commonlib::error::";
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Frame {
+  pub(super) function: String,
+  pub(super) location: Option<String>,
+}
+
+/// Captures the backtrace when `RUST_LIB_BACKTRACE` or `RUST_BACKTRACE` enable it.
+pub(super) fn capture() -> Option<Backtrace> {
+  let backtrace = Backtrace::capture();
+
+  match backtrace.status() {
+    BacktraceStatus::Captured => Some(backtrace),
+    _ => None,
+  }
+}
+
+/// The frames of the backtrace without the ones of the runtime and the error module.
+pub(super) fn get_frames(backtrace: &Backtrace) -> Vec<Frame> {
+  parse_frames(&backtrace.to_string())
+}
+
+/// Parses the frames from the text of a backtrace.
+///
+/// `std` doesn't expose the frames yet, so this relies on the format of its `Display`, which
+/// isn't stable: a numbered `N: function` line per frame, followed by `at file:line:column`
+/// lines. If the format changes, the frames are only missing from the error tree.
+fn parse_frames(text: &str) -> Vec<Frame> {
+  let mut frames: Vec<Frame> = Vec::new();
+
+  for line in text.lines() {
+    let line = line.trim();
+
+    match (line.strip_prefix("at "), frames.last_mut()) {
+      (Some(location), Some(frame)) => frame.location = Some(location.into()),
+      _ => {
+        let Some((index, function)) = line.split_once(": ") else {
+          continue;
+        };
+
+        // Like the notes about the omitted frames
+        if index.parse::<usize>().is_err() {
+          continue;
+        }
+
+        frames.push(Frame {
+          function: function.into(),
+          location: None,
+        });
+      }
+    }
+  }
+
+  frames.retain(|f| !is_ignored(&f.function));
+  frames
+}
+
+fn is_ignored(function: &str) -> bool {
+  let name = function
+    .trim_start_matches(['<', '&'])
+    .trim_start_matches("dyn ");
+
+  IGNORED_FUNCTIONS.contains(&function)
+    || IGNORED_PREFIXES.iter().any(|p| name.starts_with(p))
+    || function.contains(ERROR_MODULE)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const BACKTRACE: &str = "   0: std::backtrace_rs::backtrace::libunwind::trace
+             at /rustc/library/std/src/../../backtrace/src/backtrace/libunwind.rs:117:9
+   1: std::backtrace::Backtrace::create
+             at /rustc/library/std/src/backtrace.rs:331:13
+   2: commonlib::error::Error::new
+             at ./crates/commonlib/src/error/mod.rs:64:18
+   3: my_app::config::load
+             at ./src/config.rs:12:5
+   4: <my_app::Service as my_app::Run>::run
+   5: core::ops::function::FnOnce::call_once
+             at /rustc/library/core/src/ops/function.rs:250:5
+   6: main
+   7: __libc_start_main
+   8: _start
+note: Some details are omitted, run with `RUST_BACKTRACE=full` for a verbose backtrace.";
+
+  #[test]
+  fn parse_frames_keeps_the_frames_of_the_program() {
+    assert_eq!(
+      parse_frames(BACKTRACE),
+      [
+        Frame {
+          function: "my_app::config::load".into(),
+          location: Some("./src/config.rs:12:5".into()),
+        },
+        Frame {
+          function: "<my_app::Service as my_app::Run>::run".into(),
+          location: None,
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn parse_frames_ignores_unknown_lines() {
+    assert!(parse_frames("disabled backtrace\nat nowhere").is_empty());
+  }
+
+  #[test]
+  fn is_ignored_sees_through_the_trait_impls() {
+    assert!(is_ignored(
+      "<core::pin::Pin<P> as core::future::Future>::poll"
+    ));
+    assert!(is_ignored("<&dyn std::error::Error>::source"));
+    assert!(!is_ignored("<my_app::Service as core::ops::Drop>::drop"));
+  }
+}
@@ -0,0 +1,139 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use std::{any::Any, backtrace::Backtrace, borrow::Cow};
+
+use super::{Error, Location};
+
+/// Iterates over the layers of an error, starting with the error itself.
+///
+/// ```ignore
+/// for frame in error.chain() {
+///   println!("{} at {:?}", frame.message(), frame.location().map(|l| l.line()));
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Chain<'a> {
+  current: Option<&'a (dyn std::error::Error + 'static)>,
+}
+
+/// A layer of the error chain, which is an [`Error`] or any other error among its sources.
+#[derive(Debug, Clone, Copy)]
+pub enum ChainFrame<'a> {
+  Error(&'a Error),
+  Foreign(&'a (dyn std::error::Error + 'static)),
+}
+
+impl<'a> Chain<'a> {
+  pub fn new(error: &'a (dyn std::error::Error + 'static)) -> Self {
+    Self {
+      current: Some(error),
+    }
+  }
+}
+
+impl<'a> Iterator for Chain<'a> {
+  type Item = ChainFrame<'a>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let error = self.current?;
+    self.current = error.source();
+
+    match error.downcast_ref::<Error>() {
+      Some(error) => Some(ChainFrame::Error(error)),
+      None => Some(ChainFrame::Foreign(error)),
+    }
+  }
+}
+
+impl<'a> ChainFrame<'a> {
+  /// The message of the layer, the foreign errors are formatted.
+  pub fn message(&self) -> Cow<'a, str> {
+    match self {
+      ChainFrame::Error(error) => Cow::Borrowed(error.message()),
+      ChainFrame::Foreign(error) => Cow::Owned(error.to_string()),
+    }
+  }
+
+  pub fn category(&self) -> Option<&'a str> {
+    match self {
+      ChainFrame::Error(error) => error.category(),
+      ChainFrame::Foreign(_) => None,
+    }
+  }
+
+  pub fn code(&self) -> Option<&'a str> {
+    match self {
+      ChainFrame::Error(error) => error.code(),
+      ChainFrame::Foreign(_) => None,
+    }
+  }
+
+  /// The kind of this layer if it's of type `K`, its sources aren't looked at.
+  pub fn kind<K: Any>(&self) -> Option<&'a K> {
+    match self {
+      ChainFrame::Error(error) => error.kind.as_ref()?.downcast_ref::<K>(),
+      ChainFrame::Foreign(_) => None,
+    }
+  }
+
+  pub fn location(&self) -> Option<&'a Location> {
+    match self {
+      ChainFrame::Error(error) => error.location(),
+      ChainFrame::Foreign(_) => None,
+    }
+  }
+
+  pub fn attachments(&self) -> impl Iterator<Item = (&'a str, &'a str)> {
+    let error = match self {
+      ChainFrame::Error(error) => Some(*error),
+      ChainFrame::Foreign(_) => None,
+    };
+
+    error.into_iter().flat_map(|e| e.attachments())
+  }
+
+  pub fn notes(&self) -> &'a [String] {
+    match self {
+      ChainFrame::Error(error) => error.notes(),
+      ChainFrame::Foreign(_) => &[],
+    }
+  }
+
+  pub fn helps(&self) -> &'a [String] {
+    match self {
+      ChainFrame::Error(error) => error.helps(),
+      ChainFrame::Foreign(_) => &[],
+    }
+  }
+
+  pub fn related(&self) -> &'a [Error] {
+    match self {
+      ChainFrame::Error(error) => error.related(),
+      ChainFrame::Foreign(_) => &[],
+    }
+  }
+
+  pub fn backtrace(&self) -> Option<&'a Backtrace> {
+    match self {
+      ChainFrame::Error(error) => error.backtrace(),
+      ChainFrame::Foreign(_) => None,
+    }
+  }
+
+  /// The error of the layer if it's of type `T`, see [`Error::downcast_ref`].
+  pub fn downcast_ref<T: std::error::Error + 'static>(&self) -> Option<&'a T> {
+    match self {
+      ChainFrame::Error(error) => error.downcast_ref::<T>(),
+      ChainFrame::Foreign(error) => error.downcast_ref::<T>(),
+    }
+  }
+
+  /// The layer as a standard error, to downcast the foreign ones.
+  pub fn as_std(&self) -> &'a (dyn std::error::Error + 'static) {
+    match self {
+      ChainFrame::Error(error) => *error,
+      ChainFrame::Foreign(error) => *error,
+    }
+  }
+}
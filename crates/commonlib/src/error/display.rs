@@ -1,135 +1,272 @@
 // Copyright (c) Toolbi Software. All rights reserved.
 // Check the README file in the project root for more information.
 
-use std::error::Error as _;
+#[cfg(feature = "colorful-logs")]
+use owo_colors::OwoColorize;
 
-use super::Error;
+use super::{backtrace, Error};
 
 const INDENTATION: &str = "    ";
+const FRAME_INDENTATION: &str = "  ";
+pub(crate) const FRAME_MARK: &str = "↳";
 
 macro_rules! flatten_errors {
   ($error:expr) => {{
-    let mut result = vec![($error.message.clone(), &$error.category, &$error.location)];
-    let mut current = $error.source();
-
-    while let Some(error) = current {
-      match error.downcast_ref::<Error>() {
-        Some(error) => result.push((error.message.clone(), &error.category, &error.location)),
-        None => result.push((error.to_string(), &None, &None)),
-      };
-
-      current = error.source();
-    }
-
-    result
+    $error.chain().collect::<Vec<_>>()
   }};
 }
 
 macro_rules! group_errors {
-  ($errors:expr) => {{
+  ($errors:expr, $colored:expr, $shown_frames:expr) => {{
     let mut groups = Vec::new();
 
-    for (message, category, location) in $errors {
+    for error in $errors {
       let mut elements = Vec::new();
 
-      let category = category
-        .as_ref()
-        .map(|c| format!(" ({})", c))
+      let category = error
+        .category()
+        .map(|c| format!(" ({})", paint_category(c, $colored)))
         .unwrap_or("".into());
 
-      elements.push(format!("error{}: {}", category, message));
+      let heading = match error.code() {
+        Some(code) => format!("error[{}]", code),
+        None => "error".into(),
+      };
+
+      elements.push(format!(
+        "{}{}{} {}",
+        paint_heading(&heading, $colored),
+        category,
+        paint_heading(":", $colored),
+        error.message()
+      ));
+
+      if let Some(location) = error.location() {
+        let mut lines = vec![paint_location(
+          &format!("at {}:{}:{}", location.file, location.line, location.column),
+          $colored,
+        )];
+
+        // The frames shown by the outer errors aren't repeated, neither are the recursive ones
+        for frame in error
+          .backtrace()
+          .into_iter()
+          .flat_map(backtrace::get_frames)
+        {
+          if $shown_frames.contains(&frame) {
+            continue;
+          }
+
+          let line = match &frame.location {
+            Some(location) => format!("{} {} at {}", FRAME_MARK, frame.function, location),
+            None => format!("{} {}", FRAME_MARK, frame.function),
+          };
+
+          lines.push(format!(
+            "{}{}",
+            FRAME_INDENTATION,
+            paint_location(&line, $colored)
+          ));
+          $shown_frames.push(frame);
+        }
 
-      if let Some(location) = location {
-        elements.push(format!(
-          "at {}:{}:{}",
-          location.file, location.line, location.column
-        ));
+        elements.push(lines.join("\n"));
       }
 
-      groups.push(elements);
+      for (key, value) in error.attachments() {
+        elements.push(format!("{}: {}", paint_label(key, $colored), value));
+      }
+
+      for note in error.notes() {
+        elements.push(format!("{}: {}", paint_label("note", $colored), note));
+      }
+
+      for help in error.helps() {
+        elements.push(format!("{}: {}", paint_label("help", $colored), help));
+      }
+
+      let related = error
+        .related()
+        .iter()
+        .map(|r| get_lines(r, $colored, $shown_frames))
+        .collect();
+
+      groups.push(Group { elements, related });
     }
 
     groups
   }};
 }
 
-fn get_deepness(deepness: usize, is_title: bool) -> Option<usize> {
-  match deepness {
-    0 => None,
-    1 => match is_title {
-      true => None,
-      false => Some(1),
-    },
-    _ => match is_title {
-      true => Some(deepness - 1),
-      false => Some(deepness),
-    },
+// A layer of the chain along with the trees of its related errors
+struct Group {
+  elements: Vec<String>,
+  related: Vec<Vec<String>>,
+}
+
+// Every layer is a branch of the previous one, after the branches of its related errors
+fn format_lines(groups: Vec<Group>) -> Vec<String> {
+  let mut result = Vec::new();
+
+  for group in groups.into_iter().rev() {
+    let mut branches = group.related;
+
+    if !result.is_empty() {
+      branches.push(result);
+    }
+
+    result = format_group(group.elements, branches);
   }
+
+  result
 }
 
-fn indent_line(deepness: usize, is_title: bool) -> String {
-  let deepness = get_deepness(deepness, is_title);
+// The lines after the first one of a multi-line element, like a message or an attachment
+// value, go under its text so they don't break the tree
+fn format_group(elements: Vec<String>, branches: Vec<Vec<String>>) -> Vec<String> {
+  let mut elements = elements.into_iter();
+  let heading = elements.next().unwrap_or_default();
+  let details = elements.collect::<Vec<_>>();
+
+  let mut heading_lines = heading.lines();
+  let mut result = vec![heading_lines.next().unwrap_or_default().to_string()];
+
+  let heading_prefix = match details.is_empty() && branches.is_empty() {
+    true => "  ",
+    false => "│ ",
+  };
+  result.extend(heading_lines.map(|l| format!("{}{}", heading_prefix, l)));
+
+  for (idx, detail) in details.iter().enumerate() {
+    let is_last = details.len() == idx + 1 && branches.is_empty();
+    let mut lines = detail.lines();
+
+    match is_last {
+      true => {
+        result.push(format!("╰╴{}", lines.next().unwrap_or_default()));
+        result.extend(lines.map(|l| format!("  {}", l)));
+      }
+      false => {
+        result.push(format!("├╴{}", lines.next().unwrap_or_default()));
+        result.extend(lines.map(|l| format!("│ {}", l)));
+      }
+    }
+  }
+
+  if !details.is_empty() && !branches.is_empty() {
+    result.push("│".into());
+  }
+
+  for (idx, branch) in branches.iter().enumerate() {
+    let is_last = branches.len() == idx + 1;
+    let mut lines = branch.iter();
 
-  match deepness {
-    Some(d) => {
-      format!("{}", INDENTATION.repeat(d))
+    match is_last {
+      true => {
+        result.push(format!("╰─▶ {}", lines.next().cloned().unwrap_or_default()));
+        result.extend(lines.map(|l| format!("{}{}", INDENTATION, l)));
+      }
+      false => {
+        result.push(format!("├─▶ {}", lines.next().cloned().unwrap_or_default()));
+        result.extend(lines.map(|l| format!("│{}{}", &INDENTATION[1..], l)));
+        result.push("│".into());
+      }
     }
-    None => "".into(),
   }
+
+  result
 }
 
-fn format_lines(groups: Vec<Vec<String>>) -> Vec<String> {
-  let mut result = Vec::new();
-  let mut deepness = 0;
+fn get_lines(
+  error: &Error,
+  colored: bool,
+  shown_frames: &mut Vec<backtrace::Frame>,
+) -> Vec<String> {
+  let flattened_errors = flatten_errors!(error);
+  let grouped_errors = group_errors!(flattened_errors, colored, shown_frames);
 
-  for (idx, group) in groups.iter().enumerate() {
-    let is_first_group = idx == 0;
-    let is_last_group = groups.len() == idx + 1;
+  format_lines(grouped_errors)
+}
 
-    for (idx, element) in group.iter().enumerate() {
-      let is_first_element = idx == 0;
-      let is_last_element = group.len() == idx + 1;
+/// Renders the error tree with colors, see [`Error::display_colored`].
+#[cfg(feature = "colorful-logs")]
+pub struct DisplayColored<'a>(pub(super) &'a Error);
 
-      if is_first_element {
-        match is_first_group {
-          true => result.push(element.clone()),
-          false => result.push(format!("{}╰─▶ {}", indent_line(deepness, true), element)),
-        }
+#[cfg(feature = "colorful-logs")]
+fn paint_heading(text: &str, colored: bool) -> String {
+  match colored {
+    true => text.red().bold().to_string(),
+    false => text.into(),
+  }
+}
 
-        continue;
-      }
+#[cfg(feature = "colorful-logs")]
+fn paint_category(text: &str, colored: bool) -> String {
+  match colored {
+    true => text.yellow().bold().to_string(),
+    false => text.into(),
+  }
+}
+
+#[cfg(feature = "colorful-logs")]
+fn paint_location(text: &str, colored: bool) -> String {
+  match colored {
+    true => text.dimmed().to_string(),
+    false => text.into(),
+  }
+}
 
-      if !is_last_group || !is_last_element {
-        let indentation = indent_line(deepness, false);
+#[cfg(feature = "colorful-logs")]
+fn paint_label(text: &str, colored: bool) -> String {
+  match colored {
+    true => text.cyan().bold().to_string(),
+    false => text.into(),
+  }
+}
 
-        result.push(format!("{}├╴{}", indentation, element));
+#[cfg(not(feature = "colorful-logs"))]
+fn paint_heading(text: &str, _colored: bool) -> String {
+  text.into()
+}
 
-        if is_last_element {
-          result.push(format!("{}│", indentation));
-        }
+#[cfg(not(feature = "colorful-logs"))]
+fn paint_category(text: &str, _colored: bool) -> String {
+  text.into()
+}
 
-        continue;
-      }
+#[cfg(not(feature = "colorful-logs"))]
+fn paint_location(text: &str, _colored: bool) -> String {
+  text.into()
+}
 
-      result.push(format!("{}╰╴{}", indent_line(deepness, false), element));
-    }
+#[cfg(not(feature = "colorful-logs"))]
+fn paint_label(text: &str, _colored: bool) -> String {
+  text.into()
+}
 
-    deepness += 1;
+fn write_error(error: &Error, f: &mut std::fmt::Formatter<'_>, colored: bool) -> std::fmt::Result {
+  for line in get_lines(error, colored, &mut Vec::new()) {
+    writeln!(f, "{}", line)?;
   }
 
-  result
+  Ok(())
 }
 
+// The plain format is what ends up in the log files, so only '{:#}' looks at the terminal
 impl std::fmt::Display for Error {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    let flattened_errors = flatten_errors!(self);
-    let grouped_errors = group_errors!(flattened_errors);
+    #[cfg(feature = "colorful-logs")]
+    let colored = f.alternate() && crate::misc::supports_color();
+    #[cfg(not(feature = "colorful-logs"))]
+    let colored = false;
 
-    let lines = format_lines(grouped_errors);
-    for line in lines {
-      writeln!(f, "{}", line)?;
-    }
+    write_error(self, f, colored)
+  }
+}
 
-    Ok(())
+#[cfg(feature = "colorful-logs")]
+impl std::fmt::Display for DisplayColored<'_> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write_error(self.0, f, true)
   }
 }
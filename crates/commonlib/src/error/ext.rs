@@ -0,0 +1,186 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use super::{backtrace, has_backtrace, Error, Location, SourceKind};
+
+/// Adds context to the errors of a `Result`, or to a missing value of an `Option`.
+///
+/// Every context is a new [`Error`] layer located where the method is called, and it works with
+/// any error that can be boxed, like the standard ones, `anyhow::Error` or `eyre::Report`.
+///
+/// ```ignore
+/// let config = std::fs::read_to_string(path)
+///   .context("couldn't load the config")
+///   .category("CONFIG")?;
+/// ```
+// 'Error' carries its whole chain and is meant to be returned by value, like `Result<T, E>`
+#[allow(clippy::result_large_err)]
+pub trait ResultExt<T>: Sized {
+  /// Wraps the error as the source of a new [`Error`] with the message.
+  #[track_caller]
+  fn context(self, message: &str) -> Result<T, Error>;
+
+  /// Same as [`ResultExt::context`], but the message is only built when there is an error.
+  #[track_caller]
+  fn with_context<S: Into<String>, F: FnOnce() -> S>(self, f: F) -> Result<T, Error>;
+
+  /// Converts the error into an [`Error`] with the category.
+  ///
+  /// An [`Error`] keeps its message and location, any other error is located at the caller.
+  #[track_caller]
+  fn category(self, category: &str) -> Result<T, Error>;
+
+  /// Same as [`ResultExt::context`], it doesn't collide with `anyhow::Context`.
+  #[track_caller]
+  fn context_err(self, message: &str) -> Result<T, Error> {
+    self.context(message)
+  }
+
+  /// Same as [`ResultExt::category`].
+  #[track_caller]
+  fn with_category(self, category: &str) -> Result<T, Error> {
+    self.category(category)
+  }
+}
+
+/// Adds context to an error, turning it into a new [`Error`] layer located at the caller.
+pub trait ErrorExt: Sized {
+  #[track_caller]
+  fn context(self, message: &str) -> Error;
+
+  #[track_caller]
+  fn with_context<S: Into<String>, F: FnOnce() -> S>(self, f: F) -> Error;
+}
+
+impl<T, E> ResultExt<T> for Result<T, E>
+where
+  E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+  #[track_caller]
+  fn context(self, message: &str) -> Result<T, Error> {
+    let caller = std::panic::Location::caller();
+
+    self.map_err(|e| wrap(e.into(), message.into(), caller))
+  }
+
+  #[track_caller]
+  fn with_context<S: Into<String>, F: FnOnce() -> S>(self, f: F) -> Result<T, Error> {
+    let caller = std::panic::Location::caller();
+
+    self.map_err(|e| wrap(e.into(), f().into(), caller))
+  }
+
+  #[track_caller]
+  fn category(self, category: &str) -> Result<T, Error> {
+    let caller = std::panic::Location::caller();
+
+    self.map_err(|e| {
+      let mut error = Error::from_boxed(e.into());
+
+      if error.location.is_none() {
+        error.location = Some(Location::from(caller));
+        error.backtrace = backtrace::capture();
+      }
+
+      error.set_category(category)
+    })
+  }
+}
+
+impl<T> ResultExt<T> for Option<T> {
+  #[track_caller]
+  fn context(self, message: &str) -> Result<T, Error> {
+    let caller = std::panic::Location::caller();
+
+    self.ok_or_else(|| new(message.into(), caller))
+  }
+
+  #[track_caller]
+  fn with_context<S: Into<String>, F: FnOnce() -> S>(self, f: F) -> Result<T, Error> {
+    let caller = std::panic::Location::caller();
+
+    self.ok_or_else(|| new(f().into(), caller))
+  }
+
+  #[track_caller]
+  fn category(self, category: &str) -> Result<T, Error> {
+    let caller = std::panic::Location::caller();
+
+    self.ok_or_else(|| new("the value is missing".into(), caller).set_category(category))
+  }
+}
+
+impl<E> ErrorExt for E
+where
+  E: std::error::Error + Send + Sync + 'static,
+{
+  #[track_caller]
+  fn context(self, message: &str) -> Error {
+    wrap(
+      Box::new(self),
+      message.into(),
+      std::panic::Location::caller(),
+    )
+  }
+
+  #[track_caller]
+  fn with_context<S: Into<String>, F: FnOnce() -> S>(self, f: F) -> Error {
+    wrap(Box::new(self), f().into(), std::panic::Location::caller())
+  }
+}
+
+fn new(message: String, caller: &std::panic::Location<'_>) -> Error {
+  Error {
+    message,
+    category: None,
+    code: None,
+    kind: None,
+    location: Some(Location::from(caller)),
+    backtrace: backtrace::capture(),
+    attachments: Vec::new(),
+    notes: Vec::new(),
+    helps: Vec::new(),
+    related: Vec::new(),
+    source: None,
+  }
+}
+
+fn wrap(
+  error: Box<dyn std::error::Error + Send + Sync>,
+  message: String,
+  caller: &std::panic::Location<'_>,
+) -> Error {
+  // The backtrace of the source already goes through this layer
+  let backtrace = match has_backtrace(&*error) {
+    true => None,
+    false => backtrace::capture(),
+  };
+
+  Error {
+    backtrace,
+    source: Some(SourceKind::Error(error)),
+    ..new(message, caller)
+  }
+}
+
+#[cfg(feature = "anyhow")]
+impl From<anyhow::Error> for Error {
+  fn from(value: anyhow::Error) -> Self {
+    // 'downcast' sees through the context layers, which would be lost
+    match (*value).is::<Error>() {
+      true => value.downcast::<Error>().unwrap(),
+      false => Error::from_boxed(value.into()),
+    }
+  }
+}
+
+#[cfg(feature = "eyre")]
+impl From<eyre::Report> for Error {
+  fn from(value: eyre::Report) -> Self {
+    // 'downcast' sees through the context layers, which would be lost
+    match (*value).is::<Error>() {
+      true => value.downcast::<Error>().unwrap(),
+      false => Error::from_boxed(value.into()),
+    }
+  }
+}
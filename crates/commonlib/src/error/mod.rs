@@ -1,33 +1,54 @@
 // Copyright (c) Toolbi Software. All rights reserved.
 // Check the README file in the project root for more information.
 
-// TODO: Add 'anyhow' compatibility
-// TODO: Add color to the errors whenever the 'colorful-logs' feature is enabled
-
-use std::ops::Deref;
+use std::{any::Any, backtrace::Backtrace, ops::Deref};
 
 pub use commonlib_proc_macros::{error, errorf};
 
+pub use self::chain::{Chain, ChainFrame};
+#[cfg(feature = "colorful-logs")]
+pub use self::display::DisplayColored;
+#[cfg(feature = "logger")]
+pub(crate) use self::display::FRAME_MARK;
+pub use self::ext::{ErrorExt, ResultExt};
+
+mod backtrace;
+mod chain;
 mod display;
+mod ext;
+#[cfg(feature = "serde")]
+mod serialize;
 
 #[derive(Debug)]
 pub struct Error {
   message: String,
   category: Option<String>,
+  code: Option<String>,
+  kind: Option<Box<dyn Any + Send + Sync>>,
   location: Option<Location>,
+  backtrace: Option<Backtrace>,
+  attachments: Vec<(String, String)>,
+  notes: Vec<String>,
+  helps: Vec<String>,
+  related: Vec<Error>,
   source: Option<SourceKind>,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Location {
   file: String,
-  line: String,
-  column: String,
+  line: u32,
+  column: u32,
 }
 
+/// How the error relates to the boxed one.
 #[derive(Debug)]
 pub enum SourceKind {
+  /// The boxed error is the source, the next layer of the chain.
   Error(Box<dyn std::error::Error + Send + Sync>),
+  /// The error was converted from the boxed one and stands for it in the chain, so the next
+  /// layer is the source of the boxed error.
   FromError(Box<dyn std::error::Error + Send + Sync>),
 }
 
@@ -39,35 +60,259 @@ impl Error {
     Self {
       message: message.into(),
       category: None,
+      code: None,
+      kind: None,
       location: Some(Location::from(caller)),
+      backtrace: backtrace::capture(),
+      attachments: Vec::new(),
+      notes: Vec::new(),
+      helps: Vec::new(),
+      related: Vec::new(),
       source: None,
     }
   }
 
+  /// Converts the error, which takes its place in the chain.
+  ///
+  /// The message is the one of the error and the next layer is its source, so the error isn't
+  /// shown twice. It can be taken back with [`Error::downcast_ref`] and [`Error::downcast`].
   // TODO: Move this to a 'From' trait when the issue https://github.com/rust-lang/rust/issues/50133 is solved
   pub fn from_error<T: std::error::Error + Send + Sync + 'static>(error: T) -> Self {
-    Self {
-      message: error.to_string(),
-      category: None,
-      location: None,
-      source: Some(SourceKind::FromError(Box::new(error))),
+    Self::from_boxed(Box::new(error))
+  }
+
+  /// Converts a boxed error like [`Error::from_error`], an [`Error`] is taken back out.
+  ///
+  /// This is what the `anyhow` and `eyre` conversions go through, an [`Error`] converted into
+  /// those and back keeps its message, category, location and source.
+  pub fn from_boxed(error: Box<dyn std::error::Error + Send + Sync>) -> Self {
+    match error.downcast::<Error>() {
+      Ok(error) => *error,
+      Err(error) => Self {
+        message: error.to_string(),
+        category: None,
+        code: None,
+        kind: None,
+        location: None,
+        backtrace: None,
+        attachments: Vec::new(),
+        notes: Vec::new(),
+        helps: Vec::new(),
+        related: Vec::new(),
+        source: Some(SourceKind::FromError(error)),
+      },
     }
   }
 
+  /// Groups several errors under a new one, like the problems found while validating a config
+  /// or the failures of tasks that ran in parallel.
+  ///
+  /// ```ignore
+  /// let error = Error::from_many("the config is invalid", problems);
+  /// ```
+  #[track_caller]
+  pub fn from_many<I, E>(message: &str, errors: I) -> Self
+  where
+    I: IntoIterator<Item = E>,
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+  {
+    let mut error = Self::new(message);
+    error.related = errors
+      .into_iter()
+      .map(|e| Error::from_boxed(e.into()))
+      .collect();
+
+    error
+  }
+
+  /// Adds an error related to this one, which is shown as a sibling branch of its source.
+  pub fn push_related<E: Into<Box<dyn std::error::Error + Send + Sync>>>(
+    mut self,
+    error: E,
+  ) -> Self {
+    self.related.push(Error::from_boxed(error.into()));
+    self
+  }
+
   pub fn set_category(mut self, category: &str) -> Self {
     self.category = Some(category.into());
     self
   }
 
+  /// Sets a machine readable code, like `E_CONFIG_MISSING`, shown next to the category.
+  pub fn set_code(mut self, code: &str) -> Self {
+    self.code = Some(code.into());
+    self
+  }
+
+  /// Sets a value, usually an enum, to tell the failures apart with [`Error::is`] and
+  /// [`Error::kind`].
+  ///
+  /// ```
+  /// use commonlib::Error;
+  ///
+  /// #[derive(Debug, PartialEq)]
+  /// enum ConfigError {
+  ///   Missing,
+  ///   Invalid,
+  /// }
+  ///
+  /// let error = Error::new("the port is missing").set_kind(ConfigError::Missing);
+  ///
+  /// assert!(error.is::<ConfigError>());
+  /// assert_eq!(error.kind::<ConfigError>(), Some(&ConfigError::Missing));
+  /// assert_ne!(error.kind::<ConfigError>(), Some(&ConfigError::Invalid));
+  /// ```
+  pub fn set_kind<K: Any + Send + Sync>(mut self, kind: K) -> Self {
+    self.kind = Some(Box::new(kind));
+    self
+  }
+
+  /// Adds a piece of diagnostic data, like the path of a file or the offending value.
+  pub fn attach<V: std::fmt::Display>(mut self, key: &str, value: V) -> Self {
+    self.attachments.push((key.into(), value.to_string()));
+    self
+  }
+
+  /// Adds a note about the failure, like why it happened.
+  pub fn note(mut self, note: &str) -> Self {
+    self.notes.push(note.into());
+    self
+  }
+
+  /// Adds a hint on how to fix the failure, meant for the user.
+  pub fn help(mut self, help: &str) -> Self {
+    self.helps.push(help.into());
+    self
+  }
+
+  /// Captures the backtrace even if `RUST_LIB_BACKTRACE` and `RUST_BACKTRACE` don't enable it,
+  /// or drops it.
+  pub fn set_backtrace(mut self, enable: bool) -> Self {
+    self.backtrace = match enable {
+      true => self.backtrace.or_else(|| Some(Backtrace::force_capture())),
+      false => None,
+    };
+    self
+  }
+
+  /// The backtrace of where the error was created, rendered under its location.
+  ///
+  /// It's captured when `RUST_LIB_BACKTRACE` or `RUST_BACKTRACE` enable it, or with
+  /// [`Error::set_backtrace`]. The context layers don't capture another one when their source
+  /// already has it.
+  pub fn backtrace(&self) -> Option<&Backtrace> {
+    self.backtrace.as_ref()
+  }
+
+  pub fn message(&self) -> &str {
+    &self.message
+  }
+
+  pub fn category(&self) -> Option<&str> {
+    self.category.as_deref()
+  }
+
+  pub fn code(&self) -> Option<&str> {
+    self.code.as_deref()
+  }
+
+  /// Whether a layer of the chain has a kind of type `K`.
+  pub fn is<K: Any>(&self) -> bool {
+    self.kind::<K>().is_some()
+  }
+
+  /// The kind of type `K` of the outermost layer of the chain that has one.
+  pub fn kind<K: Any>(&self) -> Option<&K> {
+    self.chain().find_map(|f| f.kind::<K>())
+  }
+
+  /// The attachments of this layer, in the order they were added.
+  pub fn attachments(&self) -> impl Iterator<Item = (&str, &str)> {
+    self
+      .attachments
+      .iter()
+      .map(|(k, v)| (k.as_str(), v.as_str()))
+  }
+
+  /// The value of the first attachment of this layer with the key.
+  pub fn attachment(&self, key: &str) -> Option<&str> {
+    self
+      .attachments()
+      .find_map(|(k, v)| (k == key).then_some(v))
+  }
+
+  pub fn notes(&self) -> &[String] {
+    &self.notes
+  }
+
+  pub fn helps(&self) -> &[String] {
+    &self.helps
+  }
+
+  /// The errors grouped under this layer, see [`Error::from_many`].
+  pub fn related(&self) -> &[Error] {
+    &self.related
+  }
+
+  /// Where the error was created, the ones converted from other errors don't have it.
+  pub fn location(&self) -> Option<&Location> {
+    self.location.as_ref()
+  }
+
+  /// The layers of the error, from this one to the deepest source.
+  pub fn chain(&self) -> Chain<'_> {
+    Chain::new(self)
+  }
+
+  /// The error this one was converted from, if it's of type `T`.
+  ///
+  /// Only this layer is looked at, use [`ChainFrame::downcast_ref`] to look through the chain.
+  pub fn downcast_ref<T: std::error::Error + 'static>(&self) -> Option<&T> {
+    match &self.source {
+      Some(SourceKind::FromError(error)) => error.downcast_ref::<T>(),
+      _ => None,
+    }
+  }
+
+  /// Takes back the error this one was converted from, or returns the error as it was.
+  ///
+  /// The category, code and the rest added to this layer are lost.
+  #[allow(clippy::result_large_err)]
+  pub fn downcast<T: std::error::Error + 'static>(self) -> Result<T, Self> {
+    match self.source {
+      Some(SourceKind::FromError(error)) => {
+        error.downcast::<T>().map(|e| *e).map_err(|error| Self {
+          source: Some(SourceKind::FromError(error)),
+          ..self
+        })
+      }
+      _ => Err(self),
+    }
+  }
+
   /// ## WARNING!
-  /// Using this method will overwrite the previous value.
+  /// Using this method will overwrite the previous value, use [`ErrorExt::context`] or
+  /// [`ResultExt::context`] to build a chain instead.
   pub fn set_source<T: std::error::Error + Send + Sync + 'static>(mut self, error: T) -> Self {
     self.source = Some(SourceKind::Error(Box::new(error)));
     self
   }
 
+  /// Renders the error tree with red headings, highlighted categories and dimmed locations,
+  /// whether the terminal supports colors or not.
+  ///
+  /// Formatting with `{:#}` does the same only when the terminal supports colors, and `{}` is
+  /// always plain.
+  #[cfg(feature = "colorful-logs")]
+  pub fn display_colored(&self) -> DisplayColored<'_> {
+    DisplayColored(self)
+  }
+
   // Add the 'log' function
 
+  // The panic is reported at the caller, which is where the logger's panic hook points to
+  #[track_caller]
   pub fn panic(self) -> ! {
     panic!("{}", self)
   }
@@ -76,30 +321,39 @@ impl Error {
 impl std::error::Error for Error {
   fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
     match &self.source {
-      Some(source) => {
-        match source {
-          SourceKind::Error(error) => Some(error.deref()),
-          // ! Might not work
-          SourceKind::FromError(error) => error.source(),
-        }
-      }
-      &None => None,
+      Some(SourceKind::Error(error)) => Some(error.deref()),
+      // This layer stands for the error, so the chain goes on with its source
+      Some(SourceKind::FromError(error)) => error.source(),
+      None => None,
     }
   }
 }
 
+// Whether the error or any of its sources already carries a backtrace
+fn has_backtrace(error: &(dyn std::error::Error + 'static)) -> bool {
+  Chain::new(error).any(|f| f.backtrace().is_some())
+}
+
 impl Location {
-  fn new(file: String, line: String, column: String) -> Self {
+  fn new(file: String, line: u32, column: u32) -> Self {
     Self { file, line, column }
   }
+
+  pub fn file(&self) -> &str {
+    &self.file
+  }
+
+  pub fn line(&self) -> u32 {
+    self.line
+  }
+
+  pub fn column(&self) -> u32 {
+    self.column
+  }
 }
 
 impl From<&std::panic::Location<'_>> for Location {
   fn from(value: &std::panic::Location<'_>) -> Self {
-    Self::new(
-      value.file().into(),
-      value.line().to_string(),
-      value.column().to_string(),
-    )
+    Self::new(value.file().into(), value.line(), value.column())
   }
 }
@@ -0,0 +1,141 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+//! The errors are serialized as their chain, each layer nesting the next one:
+//!
+//! ```json
+//! {
+//!   "message": "couldn't start",
+//!   "category": "CONFIG",
+//!   "code": "E_CONFIG_MISSING",
+//!   "location": { "file": "src/main.rs", "line": 8, "column": 3 },
+//!   "attachments": [{ "key": "path", "value": "config.toml" }],
+//!   "notes": [],
+//!   "helps": ["create it with `init`"],
+//!   "related": [],
+//!   "source": {
+//!     "message": "No such file or directory (os error 2)",
+//!     "category": null,
+//!     "code": null,
+//!     "location": null,
+//!     "attachments": [],
+//!     "notes": [],
+//!     "helps": [],
+//!     "related": [],
+//!     "source": null
+//!   }
+//! }
+//! ```
+//!
+//! The related errors are serialized the same way, as a list of trees. The foreign errors only
+//! keep their message, so they come back as an [`Error`] without
+//! category nor location. The kinds and the backtraces aren't serialized.
+
+use std::borrow::Cow;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{Error, Location, SourceKind};
+
+#[derive(Serialize)]
+struct LayerRef<'a> {
+  message: Cow<'a, str>,
+  category: Option<&'a str>,
+  code: Option<&'a str>,
+  location: Option<&'a Location>,
+  attachments: Vec<AttachmentRef<'a>>,
+  notes: &'a [String],
+  helps: &'a [String],
+  related: Vec<LayerRef<'a>>,
+  source: Option<Box<LayerRef<'a>>>,
+}
+
+#[derive(Serialize)]
+struct AttachmentRef<'a> {
+  key: &'a str,
+  value: &'a str,
+}
+
+#[derive(Deserialize)]
+struct Layer {
+  message: String,
+  category: Option<String>,
+  // The errors serialized by the older versions don't have the fields below
+  #[serde(default)]
+  code: Option<String>,
+  location: Option<Location>,
+  #[serde(default)]
+  attachments: Vec<Attachment>,
+  #[serde(default)]
+  notes: Vec<String>,
+  #[serde(default)]
+  helps: Vec<String>,
+  #[serde(default)]
+  related: Vec<Layer>,
+  source: Option<Box<Layer>>,
+}
+
+#[derive(Deserialize)]
+struct Attachment {
+  key: String,
+  value: String,
+}
+
+impl Serialize for Error {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    get_layer(self).serialize(serializer)
+  }
+}
+
+impl<'de> Deserialize<'de> for Error {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    Layer::deserialize(deserializer).map(Error::from)
+  }
+}
+
+impl From<Layer> for Error {
+  fn from(value: Layer) -> Self {
+    Self {
+      message: value.message,
+      category: value.category,
+      code: value.code,
+      kind: None,
+      location: value.location,
+      backtrace: None,
+      attachments: value
+        .attachments
+        .into_iter()
+        .map(|a| (a.key, a.value))
+        .collect(),
+      notes: value.notes,
+      helps: value.helps,
+      related: value.related.into_iter().map(Error::from).collect(),
+      source: value
+        .source
+        .map(|s| SourceKind::Error(Box::new(Error::from(*s)))),
+    }
+  }
+}
+
+fn get_layer(error: &Error) -> LayerRef<'_> {
+  let frames = error.chain().collect::<Vec<_>>();
+
+  let layer = frames.iter().rev().fold(None, |source, frame| {
+    Some(Box::new(LayerRef {
+      message: frame.message(),
+      category: frame.category(),
+      code: frame.code(),
+      location: frame.location(),
+      attachments: frame
+        .attachments()
+        .map(|(key, value)| AttachmentRef { key, value })
+        .collect(),
+      notes: frame.notes(),
+      helps: frame.helps(),
+      related: frame.related().iter().map(get_layer).collect(),
+      source,
+    }))
+  });
+
+  *layer.expect("the chain starts with the error itself")
+}
@@ -1,7 +1,7 @@
 // Copyright (c) Toolbi Software. All rights reserved.
 // Check the README file in the project root for more information.
 
-pub use self::error::{error, errorf, Error};
+pub use self::error::{error, errorf, Error, ErrorExt, ResultExt};
 #[cfg(feature = "logger")]
 pub use self::logger::{LogLevel, Logger};
 pub use self::misc::as_variant;
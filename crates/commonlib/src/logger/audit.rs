@@ -0,0 +1,483 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+//! Tamper evident log files.
+//!
+//! In audit mode every record of the file logger stores the hash of the previous one, so
+//! editing, removing or reordering records breaks the chain. The chain continues across the
+//! daily files, and its head, the last record flushed, is kept in the `audit.head` file so
+//! removing the last records or the newest file is detected as well.
+//!
+//! With an HMAC key the hashes and the head are keyed, and each file is sealed when it's
+//! rotated, so the chain can't be rebuilt after editing the files without the key. Without a
+//! key the chain only detects accidental damage, since anyone can recompute it.
+//!
+//! ```ignore
+//! let checked = commonlib::logger::audit::verify(Path::new("logs"), Some(b"key"))?;
+//! ```
+
+use std::{
+  collections::HashSet,
+  fs::{self, OpenOptions},
+  path::{Path, PathBuf},
+};
+
+use csv::WriterBuilder;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use super::{FileLog, LogLevel};
+
+pub(super) const FILE_SUFFIX: &str = "-audit.csv";
+const SEAL_EXTENSION: &str = "seal";
+const HEAD_FILE_NAME: &str = "audit.head";
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Which logs go to the audit files, and the key of the chain.
+#[derive(Debug, Clone, Default)]
+pub(super) struct AuditRules {
+  pub(super) key: Option<Vec<u8>>,
+  /// Without categories every log is audited.
+  pub(super) categories: HashSet<String>,
+}
+
+/// Keeps track of the last hash of the chain while the audit files are written.
+#[derive(Debug)]
+pub(super) struct Chain {
+  key: Option<Vec<u8>>,
+  dir_path: PathBuf,
+  last_hash: String,
+  // The file being written, along with the amount of records in it
+  file_name: String,
+  records: usize,
+  is_head_saved: bool,
+}
+
+/// The last record of the chain that was flushed.
+#[derive(Debug, PartialEq)]
+struct Head {
+  file_name: String,
+  record: usize,
+  hash: String,
+}
+
+/// A [`FileLog`] along with the links of the chain.
+#[derive(Debug, Serialize, Deserialize)]
+struct AuditRecord {
+  timestamp: i64,
+  level: LogLevel,
+  category: Option<String>,
+  message: Option<String>,
+  stopwatch: Option<String>,
+  error: Option<String>,
+  sample_rate: Option<f64>,
+  prev_hash: String,
+  hash: String,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+  #[error("couldn't read the audit directory")]
+  ReadDir(std::io::Error),
+  #[error("couldn't read the audit file '{0}'")]
+  ReadFile(PathBuf, #[source] csv::Error),
+  #[error("couldn't hash the log")]
+  Serialize(csv::Error),
+  #[error("couldn't seal the audit file '{0}'")]
+  Seal(PathBuf, #[source] std::io::Error),
+  #[error("couldn't repair the last record of the audit file '{0}'")]
+  Repair(PathBuf, #[source] std::io::Error),
+  #[error("couldn't read the audit head '{0}'")]
+  ReadHead(PathBuf, #[source] std::io::Error),
+  #[error("couldn't save the audit head '{0}'")]
+  SaveHead(PathBuf, #[source] std::io::Error),
+  #[error("the chain is broken at the record {record} of the audit file '{path}'")]
+  BrokenLink { path: PathBuf, record: usize },
+  #[error("the audit file '{0}' isn't sealed")]
+  MissingSeal(PathBuf),
+  #[error("the seal of the audit file '{0}' doesn't match its content")]
+  BrokenSeal(PathBuf),
+  #[error("the audit head '{0}' is missing")]
+  MissingHead(PathBuf),
+  #[error("the audit head '{0}' doesn't match its content")]
+  BrokenHead(PathBuf),
+  #[error("the chain ends before its head, the record {record} of the audit file '{path}'")]
+  Truncated { path: PathBuf, record: usize },
+}
+
+impl AuditRules {
+  pub fn is_audited(&self, log: &FileLog) -> bool {
+    self.categories.is_empty()
+      || log
+        .category
+        .as_ref()
+        .is_some_and(|c| self.categories.contains(c))
+  }
+}
+
+impl Chain {
+  /// Continues the chain of the files already in the directory.
+  ///
+  /// The last record of the newest file is cut if the process stopped while writing it. It
+  /// fails if the files end before the head, since the last records were removed.
+  ///
+  /// With a key, the files other than the current one that were left unsealed, because the
+  /// process stopped before rotating them, are sealed.
+  pub fn resume(dir_path: &Path, current_path: &Path, key: Option<Vec<u8>>) -> Result<Self, Error> {
+    let paths = list_files(dir_path)?;
+
+    if let Some(path) = paths.last() {
+      repair(path)?;
+    }
+
+    let mut last_hash = GENESIS_HASH.to_string();
+
+    // The newest file may not have any record yet
+    for path in paths.iter().rev() {
+      if let Some(record) = read_records(path)?.pop() {
+        last_hash = record.hash;
+        break;
+      }
+    }
+
+    match read_head(dir_path, key.as_deref())? {
+      Some(head) => check_head(dir_path, &head)?,
+      None if last_hash != GENESIS_HASH => return Err(Error::MissingHead(get_head_path(dir_path))),
+      None => {}
+    }
+
+    let records = match paths.iter().any(|p| p == current_path) {
+      true => read_records(current_path)?.len(),
+      false => 0,
+    };
+
+    let chain = Self {
+      key,
+      dir_path: dir_path.into(),
+      last_hash,
+      file_name: get_file_name(current_path),
+      records,
+      is_head_saved: true,
+    };
+
+    if chain.key.is_some() {
+      for path in paths.iter().filter(|p| *p != current_path) {
+        if !get_seal_path(path).exists() {
+          chain.seal(path)?;
+        }
+      }
+    }
+
+    Ok(chain)
+  }
+
+  /// Links the log to the previous one, returning the record to write.
+  pub fn link(&mut self, log: FileLog) -> Result<impl Serialize, Error> {
+    let hash = hash_log(self.key.as_deref(), &self.last_hash, &log)?;
+    let prev_hash = std::mem::replace(&mut self.last_hash, hash.clone());
+
+    self.records += 1;
+    self.is_head_saved = false;
+
+    Ok(AuditRecord::new(log, prev_hash, hash))
+  }
+
+  /// Continues the chain in a new file, once the previous one is rotated.
+  pub fn start_file(&mut self, path: &Path) {
+    self.file_name = get_file_name(path);
+    self.records = 0;
+  }
+
+  /// Saves the last record linked as the head, it must be called once the records are flushed.
+  pub fn save_head(&mut self) -> Result<(), Error> {
+    if self.is_head_saved || self.records == 0 {
+      return Ok(());
+    }
+
+    let head = Head {
+      file_name: self.file_name.clone(),
+      record: self.records,
+      hash: self.last_hash.clone(),
+    };
+
+    // Written aside and then renamed, so a crash never leaves half a head
+    let path = get_head_path(&self.dir_path);
+    let temp_path = path.with_extension("head.tmp");
+
+    fs::write(&temp_path, head.to_string(self.key.as_deref()))
+      .and_then(|_| fs::rename(&temp_path, &path))
+      .map_err(|e| Error::SaveHead(path, e))?;
+
+    self.is_head_saved = true;
+    Ok(())
+  }
+
+  /// Writes the HMAC of the file next to it, nothing is done without a key.
+  pub fn seal(&self, path: &Path) -> Result<(), Error> {
+    let Some(key) = &self.key else {
+      return Ok(());
+    };
+
+    let content = fs::read(path).map_err(|e| Error::Seal(path.into(), e))?;
+
+    fs::write(get_seal_path(path), get_hmac(key, &content)).map_err(|e| Error::Seal(path.into(), e))
+  }
+}
+
+impl Head {
+  fn get_content(&self) -> String {
+    format!("{},{},{}", self.file_name, self.record, self.hash)
+  }
+
+  /// The head is followed by its HMAC when there is a key.
+  fn to_string(&self, key: Option<&[u8]>) -> String {
+    let content = self.get_content();
+    let hmac = key
+      .map(|k| get_hmac(k, content.as_bytes()))
+      .unwrap_or_default();
+
+    format!("{},{}\n", content, hmac)
+  }
+
+  fn parse(string: &str, key: Option<&[u8]>) -> Option<Self> {
+    let mut parts = string.trim().split(',');
+
+    let head = Self {
+      file_name: parts.next()?.into(),
+      record: parts.next()?.parse().ok()?,
+      hash: parts.next()?.into(),
+    };
+    let hmac = parts.next()?;
+
+    match key {
+      Some(key) if hmac != get_hmac(key, head.get_content().as_bytes()) => None,
+      _ => Some(head),
+    }
+  }
+}
+
+impl AuditRecord {
+  fn new(log: FileLog, prev_hash: String, hash: String) -> Self {
+    Self {
+      timestamp: log.timestamp,
+      level: log.level,
+      category: log.category,
+      message: log.message,
+      stopwatch: log.stopwatch,
+      error: log.error,
+      sample_rate: log.sample_rate,
+      prev_hash,
+      hash,
+    }
+  }
+
+  fn to_log(&self) -> FileLog {
+    FileLog {
+      timestamp: self.timestamp,
+      level: self.level,
+      category: self.category.clone(),
+      message: self.message.clone(),
+      stopwatch: self.stopwatch.clone(),
+      error: self.error.clone(),
+      sample_rate: self.sample_rate,
+    }
+  }
+}
+
+/// Checks the chain of every audit file in the directory, oldest first, up to its head.
+///
+/// With a key, every file but the newest one must have a valid seal, and the chain and the head
+/// must have been written with the same key. Returns the amount of records checked, or the
+/// first broken link.
+pub fn verify(dir_path: &Path, hmac_key: Option<&[u8]>) -> Result<usize, Error> {
+  let paths = list_files(dir_path)?;
+  let head = read_head(dir_path, hmac_key)?;
+  let mut last_hash = GENESIS_HASH.to_string();
+  let mut checked = 0;
+  let mut is_head_found = false;
+
+  for (idx, path) in paths.iter().enumerate() {
+    if let Some(key) = hmac_key {
+      let is_current = idx == paths.len() - 1;
+
+      match fs::read_to_string(get_seal_path(path)) {
+        Ok(seal) => {
+          let content = fs::read(path).map_err(|e| Error::ReadFile(path.clone(), e.into()))?;
+
+          if seal.trim() != get_hmac(key, &content) {
+            return Err(Error::BrokenSeal(path.clone()));
+          }
+        }
+        Err(_) if is_current => {}
+        Err(_) => return Err(Error::MissingSeal(path.clone())),
+      }
+    }
+
+    let file_name = get_file_name(path);
+
+    for (record_idx, record) in read_records(path)?.into_iter().enumerate() {
+      let is_valid = record.prev_hash == last_hash
+        && record.hash == hash_log(hmac_key, &last_hash, &record.to_log())?;
+
+      if !is_valid {
+        return Err(Error::BrokenLink {
+          path: path.clone(),
+          record: record_idx + 1,
+        });
+      }
+
+      // The records after the head were written since the last time it was saved
+      is_head_found |= head.as_ref().is_some_and(|h| {
+        h.file_name == file_name && h.record == record_idx + 1 && h.hash == record.hash
+      });
+
+      last_hash = record.hash;
+      checked += 1;
+    }
+  }
+
+  match head {
+    Some(head) if !is_head_found => Err(Error::Truncated {
+      path: dir_path.join(head.file_name),
+      record: head.record,
+    }),
+    None if checked > 0 => Err(Error::MissingHead(get_head_path(dir_path))),
+    _ => Ok(checked),
+  }
+}
+
+/// The audit files of the directory, sorted from oldest to newest.
+fn list_files(dir_path: &Path) -> Result<Vec<PathBuf>, Error> {
+  let mut paths = fs::read_dir(dir_path)
+    .map_err(Error::ReadDir)?
+    .filter_map(|e| e.ok().map(|e| e.path()))
+    .filter(|p| p.is_file() && p.to_string_lossy().ends_with(FILE_SUFFIX))
+    .collect::<Vec<_>>();
+  paths.sort();
+
+  Ok(paths)
+}
+
+/// Cuts the last record of the file if it's incomplete, like after a crash while writing it.
+fn repair(path: &Path) -> Result<(), Error> {
+  let content = fs::read(path).map_err(|e| Error::Repair(path.into(), e))?;
+  let mut reader = csv::Reader::from_reader(content.as_slice());
+  let mut record = csv::StringRecord::new();
+  // Where the last complete record, or the header, ends
+  let mut end = 0;
+
+  if reader.headers().is_ok() {
+    end = reader.position().byte();
+
+    while let Ok(true) = reader.read_record(&mut record) {
+      end = reader.position().byte();
+    }
+  }
+
+  // A row without its line break may still be missing part of its last field
+  if end == content.len() as u64 && !content.ends_with(b"\n") {
+    end = record.position().map(|p| p.byte()).unwrap_or(0);
+  }
+
+  if end == content.len() as u64 {
+    return Ok(());
+  }
+
+  OpenOptions::new()
+    .write(true)
+    .open(path)
+    .and_then(|f| f.set_len(end))
+    .map_err(|e| Error::Repair(path.into(), e))
+}
+
+fn read_head(dir_path: &Path, key: Option<&[u8]>) -> Result<Option<Head>, Error> {
+  let path = get_head_path(dir_path);
+
+  let string = match fs::read_to_string(&path) {
+    Ok(string) => string,
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+    Err(e) => return Err(Error::ReadHead(path, e)),
+  };
+
+  match Head::parse(&string, key) {
+    Some(head) => Ok(Some(head)),
+    None => Err(Error::BrokenHead(path)),
+  }
+}
+
+/// Checks that the files still reach the head.
+fn check_head(dir_path: &Path, head: &Head) -> Result<(), Error> {
+  let path = dir_path.join(&head.file_name);
+  let records = match path.exists() {
+    true => read_records(&path)?,
+    false => Vec::new(),
+  };
+
+  match records.get(head.record.wrapping_sub(1)) {
+    Some(record) if record.hash == head.hash => Ok(()),
+    _ => Err(Error::Truncated {
+      path,
+      record: head.record,
+    }),
+  }
+}
+
+fn read_records(path: &Path) -> Result<Vec<AuditRecord>, Error> {
+  let mut reader = csv::Reader::from_path(path).map_err(|e| Error::ReadFile(path.into(), e))?;
+
+  reader
+    .deserialize()
+    .collect::<Result<Vec<AuditRecord>, _>>()
+    .map_err(|e| Error::ReadFile(path.into(), e))
+}
+
+// The hash covers the CSV row of the log, which is what ends up in the file
+fn hash_log(key: Option<&[u8]>, prev_hash: &str, log: &FileLog) -> Result<String, Error> {
+  let mut writer = WriterBuilder::new()
+    .has_headers(false)
+    .from_writer(Vec::new());
+  writer.serialize(log).map_err(Error::Serialize)?;
+
+  let row = writer
+    .into_inner()
+    .map_err(|e| Error::Serialize(e.into_error().into()))?;
+
+  let content = [prev_hash.as_bytes(), &row].concat();
+
+  match key {
+    Some(key) => Ok(get_hmac(key, &content)),
+    None => Ok(to_hex(&Sha256::digest(&content))),
+  }
+}
+
+fn get_hmac(key: &[u8], content: &[u8]) -> String {
+  let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+  mac.update(content);
+
+  to_hex(&mac.finalize().into_bytes())
+}
+
+fn get_head_path(dir_path: &Path) -> PathBuf {
+  dir_path.join(HEAD_FILE_NAME)
+}
+
+fn get_file_name(path: &Path) -> String {
+  path
+    .file_name()
+    .map(|n| n.to_string_lossy().to_string())
+    .unwrap_or_default()
+}
+
+fn get_seal_path(path: &Path) -> PathBuf {
+  let mut result = path.as_os_str().to_owned();
+  result.push(".");
+  result.push(SEAL_EXTENSION);
+
+  result.into()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
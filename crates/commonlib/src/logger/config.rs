@@ -0,0 +1,640 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use std::{collections::HashMap, env, path::Path, time::Duration};
+
+#[cfg(not(all(
+  unix,
+  feature = "otel",
+  feature = "logger-network",
+  feature = "logger-redaction"
+)))]
+use serde::de::IgnoredAny;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{Map, Value};
+
+#[cfg(feature = "logger-redaction")]
+use super::Masking;
+#[cfg(feature = "otel")]
+use super::OtelProtocol;
+use super::{ConsoleWrap, LogLevel, Logger, RateLimit, Sampling, Unlocked};
+use crate::Error;
+
+const ENV_PREFIX: &str = "COMMONLIB_LOG_";
+
+/// The settings of a [`Logger`], usually loaded from a file and the environment.
+///
+/// Every setting is optional, the ones that are missing keep the default of the builder.
+///
+/// The settings that aren't available, like `syslog_logger` outside of unix, `otel_exporter`
+/// without the `otel` feature or `file_logger.audit` without the `logger-audit` feature, are
+/// accepted and ignored, so the same config can be shared between platforms and builds.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggerConfig {
+  pub level: Option<LogLevel>,
+  pub module_filters: HashMap<String, LogLevel>,
+  pub log_bridge: Option<bool>,
+  pub console: ConsoleConfig,
+  pub file_logger: FileLoggerConfig,
+  #[cfg(feature = "logger-network")]
+  pub network_logger: NetworkLoggerConfig,
+  #[cfg(not(feature = "logger-network"))]
+  pub network_logger: IgnoredAny,
+  #[cfg(unix)]
+  pub syslog_logger: SyslogLoggerConfig,
+  #[cfg(not(unix))]
+  pub syslog_logger: IgnoredAny,
+  #[cfg(unix)]
+  pub journald_logger: JournaldLoggerConfig,
+  #[cfg(not(unix))]
+  pub journald_logger: IgnoredAny,
+  #[cfg(feature = "otel")]
+  pub otel_exporter: OtelExporterConfig,
+  #[cfg(not(feature = "otel"))]
+  pub otel_exporter: IgnoredAny,
+  pub limiter: LimiterConfig,
+  pub sampler: SamplerConfig,
+  #[cfg(feature = "logger-redaction")]
+  pub redaction: RedactionConfig,
+  #[cfg(not(feature = "logger-redaction"))]
+  pub redaction: IgnoredAny,
+  pub ring_buffer: RingBufferConfig,
+  pub panic_hook: PanicHookConfig,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConsoleConfig {
+  pub wrap: Option<ConsoleWrap>,
+  pub width: Option<usize>,
+  pub compact_errors: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileLoggerConfig {
+  pub enable: Option<bool>,
+  pub path: Option<String>,
+  pub audit: Option<bool>,
+  /// The HMAC key of the audit chain, see [`Logger::setup_file_audit`].
+  pub audit_key: Option<String>,
+  /// The categories that are audited, every one when it's empty.
+  pub audit_categories: Vec<String>,
+}
+
+#[cfg(feature = "logger-network")]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkLoggerConfig {
+  pub enable: Option<bool>,
+  pub endpoint: Option<String>,
+  pub spool_path: Option<String>,
+}
+
+#[cfg(unix)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SyslogLoggerConfig {
+  pub enable: Option<bool>,
+  pub path: Option<String>,
+  pub facility: Option<u8>,
+}
+
+#[cfg(unix)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JournaldLoggerConfig {
+  pub enable: Option<bool>,
+  pub path: Option<String>,
+}
+
+#[cfg(feature = "otel")]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OtelExporterConfig {
+  pub enable: Option<bool>,
+  pub endpoint: Option<String>,
+  pub protocol: Option<OtelProtocol>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimiterConfig {
+  pub rate_limit: Option<RateLimitConfig>,
+  /// A `null` limit removes the limit of the category.
+  pub category_rate_limits: HashMap<String, Option<RateLimitConfig>>,
+  pub dedup: Option<bool>,
+  pub category_dedups: HashMap<String, bool>,
+}
+
+/// A [`RateLimit`] with its interval in milliseconds.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitConfig {
+  pub count: u32,
+  pub interval_ms: u64,
+}
+
+/// The samplings are written like `{ probability = 0.1 }` or `{ every_nth = 10 }`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SamplerConfig {
+  pub level_samplings: HashMap<LogLevel, Sampling>,
+  /// The samplings of each module by level, a `null` sampling keeps all the logs.
+  pub module_samplings: HashMap<String, HashMap<LogLevel, Option<Sampling>>>,
+}
+
+#[cfg(feature = "logger-redaction")]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedactionConfig {
+  pub fields: Vec<RedactedFieldConfig>,
+  pub patterns: Vec<RedactionPatternConfig>,
+}
+
+#[cfg(feature = "logger-redaction")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RedactedFieldConfig {
+  pub name: String,
+  #[serde(default)]
+  pub masking: Masking,
+}
+
+#[cfg(feature = "logger-redaction")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RedactionPatternConfig {
+  pub pattern: String,
+  #[serde(default)]
+  pub masking: Masking,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RingBufferConfig {
+  pub enable: Option<bool>,
+  pub level: Option<LogLevel>,
+  pub capacity: Option<usize>,
+  pub dump_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PanicHookConfig {
+  pub enable: Option<bool>,
+  pub backtrace: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigFormat {
+  Toml,
+  Json,
+  Yaml,
+}
+
+// 'Error' carries its whole chain and is meant to be returned by value
+#[allow(clippy::result_large_err)]
+impl LoggerConfig {
+  /// Loads the file and then applies the `COMMONLIB_LOG_*` environment variables on top.
+  pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+    Self::from_file(path)?.with_env()
+  }
+
+  /// Reads a TOML, JSON or YAML file, chosen by its extension.
+  pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+    let path = path.as_ref();
+
+    let format = match path.extension().and_then(|e| e.to_str()) {
+      Some("toml") => ConfigFormat::Toml,
+      Some("json") => ConfigFormat::Json,
+      Some("yaml" | "yml") => ConfigFormat::Yaml,
+      _ => {
+        return Err(config_error(&format!(
+          "the config file '{}' must have a 'toml', 'json' or 'yaml' extension",
+          path.display()
+        )))
+      }
+    };
+
+    let string = std::fs::read_to_string(path).map_err(|e| {
+      config_error(&format!(
+        "couldn't read the config file '{}'",
+        path.display()
+      ))
+      .set_source(e)
+    })?;
+
+    Self::parse(&string, format)
+  }
+
+  pub fn parse(string: &str, format: ConfigFormat) -> Result<Self, Error> {
+    let value = match format {
+      ConfigFormat::Toml => toml::from_str::<Value>(string)
+        .map_err(|e| config_error("couldn't parse the TOML config").set_source(e)),
+      ConfigFormat::Json => serde_json::from_str::<Value>(string)
+        .map_err(|e| config_error("couldn't parse the JSON config").set_source(e)),
+      ConfigFormat::Yaml => serde_yaml::from_str::<Value>(string)
+        .map_err(|e| config_error("couldn't parse the YAML config").set_source(e)),
+    }?;
+
+    deserialize(value)
+  }
+
+  /// Applies the `COMMONLIB_LOG_*` environment variables on top of the config.
+  ///
+  /// The variables are named after the keys, like `COMMONLIB_LOG_LEVEL` or
+  /// `COMMONLIB_LOG_FILE_LOGGER_PATH`, and `COMMONLIB_LOG_MODULE_FILTERS` takes a list like
+  /// `my_crate::db=debug,my_crate::http=warn`. The maps and the lists, like
+  /// `COMMONLIB_LOG_REDACTION_FIELDS`, are written in JSON.
+  pub fn with_env(mut self) -> Result<Self, Error> {
+    let vars = env::vars()
+      .filter(|(key, _)| key.starts_with(ENV_PREFIX))
+      .collect::<Vec<_>>();
+
+    for (key, value) in vars {
+      let name = key[ENV_PREFIX.len()..].to_lowercase();
+
+      let candidates = match name.as_str() {
+        "module_filters" => {
+          let mut filters = Map::new();
+
+          for filter in value.split(',').filter(|f| !f.trim().is_empty()) {
+            let (module, level) = filter.split_once('=').ok_or(config_error(&format!(
+              "the filter '{}' of '{}' must look like 'module=level'",
+              filter, key
+            )))?;
+
+            filters.insert(module.trim().into(), Value::String(level.trim().into()));
+          }
+
+          vec![Value::Object(filters)]
+        }
+        _ => get_env_candidates(&value),
+      };
+
+      let section = [
+        "console",
+        "file_logger",
+        "network_logger",
+        "syslog_logger",
+        "journald_logger",
+        "otel_exporter",
+        "limiter",
+        "sampler",
+        "redaction",
+        "ring_buffer",
+        "panic_hook",
+      ]
+      .into_iter()
+      .find(|s| name.starts_with(&format!("{}_", s)));
+
+      let to_patch = |value: Value| match section {
+        Some(section) => Map::from_iter([(
+          section.to_string(),
+          Value::Object(Map::from_iter([(
+            name[section.len() + 1..].to_string(),
+            value,
+          )])),
+        )]),
+        None => Map::from_iter([(name.clone(), value)]),
+      };
+
+      let mut patch = None;
+      let mut error = None;
+
+      for candidate in candidates {
+        match deserialize::<Self>(Value::Object(to_patch(candidate))) {
+          Ok(value) => {
+            patch = Some(value);
+            break;
+          }
+          Err(e) => {
+            error.get_or_insert(e);
+          }
+        }
+      }
+
+      match (patch, error) {
+        (Some(patch), _) => self.merge(patch),
+        (None, error) => {
+          let mut result =
+            config_error(&format!("the environment variable '{}' is not valid", key));
+
+          if let Some(error) = error {
+            result = result.set_source(error);
+          }
+
+          return Err(result);
+        }
+      }
+    }
+
+    Ok(self)
+  }
+
+  fn merge(&mut self, other: Self) {
+    self.level = other.level.or(self.level);
+    self.module_filters.extend(other.module_filters);
+    self.log_bridge = other.log_bridge.or(self.log_bridge);
+
+    let console = other.console;
+    self.console.wrap = console.wrap.or(self.console.wrap);
+    self.console.width = console.width.or(self.console.width);
+    self.console.compact_errors = console.compact_errors.or(self.console.compact_errors);
+
+    let file = other.file_logger;
+    self.file_logger.enable = file.enable.or(self.file_logger.enable);
+    self.file_logger.path = file.path.or(self.file_logger.path.take());
+    self.file_logger.audit = file.audit.or(self.file_logger.audit);
+    self.file_logger.audit_key = file.audit_key.or(self.file_logger.audit_key.take());
+    self
+      .file_logger
+      .audit_categories
+      .extend(file.audit_categories);
+
+    #[cfg(feature = "logger-network")]
+    {
+      let network = other.network_logger;
+      self.network_logger.enable = network.enable.or(self.network_logger.enable);
+      self.network_logger.endpoint = network.endpoint.or(self.network_logger.endpoint.take());
+      self.network_logger.spool_path = network.spool_path.or(self.network_logger.spool_path.take());
+    }
+
+    #[cfg(unix)]
+    {
+      let syslog = other.syslog_logger;
+      self.syslog_logger.enable = syslog.enable.or(self.syslog_logger.enable);
+      self.syslog_logger.path = syslog.path.or(self.syslog_logger.path.take());
+      self.syslog_logger.facility = syslog.facility.or(self.syslog_logger.facility);
+
+      let journald = other.journald_logger;
+      self.journald_logger.enable = journald.enable.or(self.journald_logger.enable);
+      self.journald_logger.path = journald.path.or(self.journald_logger.path.take());
+    }
+
+    #[cfg(feature = "otel")]
+    {
+      let otel = other.otel_exporter;
+      self.otel_exporter.enable = otel.enable.or(self.otel_exporter.enable);
+      self.otel_exporter.endpoint = otel.endpoint.or(self.otel_exporter.endpoint.take());
+      self.otel_exporter.protocol = otel.protocol.or(self.otel_exporter.protocol);
+    }
+
+    let limiter = other.limiter;
+    self.limiter.rate_limit = limiter.rate_limit.or(self.limiter.rate_limit);
+    self
+      .limiter
+      .category_rate_limits
+      .extend(limiter.category_rate_limits);
+    self.limiter.dedup = limiter.dedup.or(self.limiter.dedup);
+    self.limiter.category_dedups.extend(limiter.category_dedups);
+
+    let sampler = other.sampler;
+    self.sampler.level_samplings.extend(sampler.level_samplings);
+
+    for (module_name, samplings) in sampler.module_samplings {
+      self
+        .sampler
+        .module_samplings
+        .entry(module_name)
+        .or_default()
+        .extend(samplings);
+    }
+
+    #[cfg(feature = "logger-redaction")]
+    {
+      self.redaction.fields.extend(other.redaction.fields);
+      self.redaction.patterns.extend(other.redaction.patterns);
+    }
+
+    let ring = other.ring_buffer;
+    self.ring_buffer.enable = ring.enable.or(self.ring_buffer.enable);
+    self.ring_buffer.level = ring.level.or(self.ring_buffer.level);
+    self.ring_buffer.capacity = ring.capacity.or(self.ring_buffer.capacity);
+    self.ring_buffer.dump_path = ring.dump_path.or(self.ring_buffer.dump_path.take());
+
+    let panic = other.panic_hook;
+    self.panic_hook.enable = panic.enable.or(self.panic_hook.enable);
+    self.panic_hook.backtrace = panic.backtrace.or(self.panic_hook.backtrace);
+  }
+}
+
+impl Logger {
+  /// Creates a logger from the config, the builder methods called afterwards take precedence.
+  pub fn from_config(config: LoggerConfig) -> Logger<Unlocked> {
+    let mut logger = Logger::new();
+
+    if let Some(level) = config.level {
+      logger.level = level;
+    }
+
+    logger.module_filters.extend(config.module_filters);
+
+    #[cfg(feature = "log-bridge")]
+    if let Some(log_bridge) = config.log_bridge {
+      logger.log_bridge = log_bridge;
+    }
+
+    let console = config.console;
+    logger.console_layout.wrap = console.wrap.unwrap_or(logger.console_layout.wrap);
+    logger.console_layout.width = console.width.or(logger.console_layout.width);
+    logger.console_layout.compact_errors = console
+      .compact_errors
+      .unwrap_or(logger.console_layout.compact_errors);
+
+    let file = config.file_logger;
+    logger.file_logger.enable = file.enable.unwrap_or(logger.file_logger.enable);
+    logger.file_logger.path = file.path.unwrap_or(logger.file_logger.path);
+
+    #[cfg(feature = "logger-audit")]
+    {
+      logger.file_logger.audit = file.audit.unwrap_or(logger.file_logger.audit);
+      logger.file_logger.audit_key = file
+        .audit_key
+        .map(|k| k.into_bytes())
+        .or(logger.file_logger.audit_key);
+
+      for category in file.audit_categories {
+        logger = logger.add_audit_category(&category);
+      }
+    }
+
+    #[cfg(feature = "logger-network")]
+    {
+      let network = config.network_logger;
+      logger.network_logger.enable = network.enable.unwrap_or(logger.network_logger.enable);
+      logger.network_logger.endpoint = network.endpoint.unwrap_or(logger.network_logger.endpoint);
+      logger.network_logger.spool_path = network
+        .spool_path
+        .unwrap_or(logger.network_logger.spool_path);
+    }
+
+    #[cfg(unix)]
+    {
+      let syslog = config.syslog_logger;
+      logger.syslog_logger.enable = syslog.enable.unwrap_or(logger.syslog_logger.enable);
+      logger.syslog_logger.path = syslog.path.unwrap_or(logger.syslog_logger.path);
+      logger.syslog_logger.facility = syslog.facility.unwrap_or(logger.syslog_logger.facility);
+
+      let journald = config.journald_logger;
+      logger.journald_logger.enable = journald.enable.unwrap_or(logger.journald_logger.enable);
+      logger.journald_logger.path = journald.path.unwrap_or(logger.journald_logger.path);
+    }
+
+    #[cfg(feature = "otel")]
+    {
+      let otel = config.otel_exporter;
+      logger.otel_exporter.enable = otel.enable.unwrap_or(logger.otel_exporter.enable);
+      logger.otel_exporter.endpoint = otel.endpoint.or(logger.otel_exporter.endpoint);
+      logger.otel_exporter.protocol = otel.protocol.unwrap_or(logger.otel_exporter.protocol);
+    }
+
+    let limiter = config.limiter;
+
+    if let Some(rate_limit) = limiter.rate_limit {
+      logger.limiter.rate_limit = Some(rate_limit.into());
+    }
+
+    for (category, rate_limit) in limiter.category_rate_limits {
+      logger
+        .limiter
+        .category_rate_limits
+        .insert(category.to_uppercase(), rate_limit.map(|r| r.into()));
+    }
+
+    logger.limiter.dedup = limiter.dedup.unwrap_or(logger.limiter.dedup);
+
+    for (category, dedup) in limiter.category_dedups {
+      logger
+        .limiter
+        .category_dedups
+        .insert(category.to_uppercase(), dedup);
+    }
+
+    let sampler = config.sampler;
+    logger
+      .sampler
+      .level_samplings
+      .extend(sampler.level_samplings);
+
+    for (module_name, samplings) in sampler.module_samplings {
+      for (level, sampling) in samplings {
+        logger
+          .sampler
+          .module_samplings
+          .insert((module_name.clone(), level), sampling);
+      }
+    }
+
+    #[cfg(feature = "logger-redaction")]
+    {
+      let redaction = config.redaction;
+
+      for field in redaction.fields {
+        logger.redaction.fields.push((field.name, field.masking));
+      }
+
+      for pattern in redaction.patterns {
+        logger
+          .redaction
+          .patterns
+          .push((pattern.pattern, pattern.masking));
+      }
+    }
+
+    let ring = config.ring_buffer;
+    logger.ring_buffer.enable = ring.enable.unwrap_or(logger.ring_buffer.enable);
+    logger.ring_buffer.level = ring.level.unwrap_or(logger.ring_buffer.level);
+    logger.ring_buffer.capacity = ring.capacity.unwrap_or(logger.ring_buffer.capacity).max(1);
+    logger.ring_buffer.dump_path = ring.dump_path.or(logger.ring_buffer.dump_path);
+
+    let panic = config.panic_hook;
+    logger.panic_hook.enable = panic.enable.unwrap_or(logger.panic_hook.enable);
+    logger.panic_hook.backtrace = panic.backtrace.unwrap_or(logger.panic_hook.backtrace);
+
+    logger
+  }
+}
+
+impl From<RateLimitConfig> for RateLimit {
+  fn from(value: RateLimitConfig) -> Self {
+    Self {
+      count: value.count,
+      interval: Duration::from_millis(value.interval_ms),
+    }
+  }
+}
+
+#[track_caller]
+fn config_error(message: &str) -> Error {
+  Error::new(message).set_category("CONFIG")
+}
+
+/// Environment variables are always strings, so they are tried as booleans, numbers and JSON
+/// maps or lists as well.
+fn get_env_candidates(value: &str) -> Vec<Value> {
+  let mut candidates = vec![Value::String(value.into())];
+
+  match value.to_lowercase().as_str() {
+    "true" | "1" | "yes" => candidates.push(Value::Bool(true)),
+    "false" | "0" | "no" => candidates.push(Value::Bool(false)),
+    _ => {}
+  }
+
+  if let Ok(number) = value.parse::<u64>() {
+    candidates.push(Value::from(number));
+  }
+
+  if let Ok(json @ (Value::Object(_) | Value::Array(_))) = serde_json::from_str(value) {
+    candidates.push(json);
+  }
+
+  candidates
+}
+
+#[allow(clippy::result_large_err)]
+fn deserialize<T: DeserializeOwned>(value: Value) -> Result<T, Error> {
+  serde_json::from_value::<T>(value.clone()).map_err(|error| {
+    let key = find_key::<T>(&|v| v, &value).unwrap_or_default();
+
+    match key.is_empty() {
+      true => config_error("the config is not valid").set_source(error),
+      false => config_error(&format!("the config key '{}' is not valid", key)).set_source(error),
+    }
+  })
+}
+
+/// Narrows down the key that makes the deserialization fail, the one that changes the error
+/// when it's removed.
+fn find_key<T: DeserializeOwned>(wrap: &dyn Fn(Value) -> Value, value: &Value) -> Option<String> {
+  let Value::Object(map) = value else {
+    return None;
+  };
+
+  let get_error = |fields: Map<String, Value>| {
+    serde_json::from_value::<T>(wrap(Value::Object(fields)))
+      .err()
+      .map(|e| e.to_string())
+  };
+
+  let error = get_error(map.clone());
+
+  let key = map.keys().find(|k| {
+    let mut rest = map.clone();
+    rest.remove(*k);
+    get_error(rest) != error
+  })?;
+
+  let wrap_field = |v: Value| {
+    let mut fields = map.clone();
+    fields.insert(key.clone(), v);
+    wrap(Value::Object(fields))
+  };
+
+  match find_key::<T>(&wrap_field, &map[key]) {
+    Some(inner) => Some(format!("{}.{}", key, inner)),
+    None => Some(key.clone()),
+  }
+}
@@ -0,0 +1,307 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use std::env;
+
+use serde::Deserialize;
+#[cfg(feature = "logger-console-width")]
+use terminal_size::{terminal_size_of, Width};
+
+use super::LogLevel;
+use crate::str::Trunc;
+
+// Below this the lines would be too short to read
+const MIN_WIDTH: usize = 20;
+
+/// How the console lines that don't fit in the terminal are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsoleWrap {
+  /// The lines are printed whole and the terminal wraps them.
+  #[default]
+  Off,
+  /// The lines are cut at the terminal width, ending with `…`.
+  Truncate,
+  /// The lines are wrapped at the terminal width, aligned under the message.
+  Wrap,
+}
+
+/// Fits the message and the error of the console lines to the terminal.
+#[derive(Debug, Clone, Default)]
+pub struct ConsoleLayout {
+  pub(super) wrap: ConsoleWrap,
+  pub(super) width: Option<usize>,
+  pub(super) compact_errors: bool,
+}
+
+impl ConsoleLayout {
+  /// Lays out the message, which starts at `column` and is followed by `reserved` characters.
+  pub fn format_message(
+    &self,
+    level: LogLevel,
+    column: usize,
+    reserved: usize,
+    message: &str,
+  ) -> String {
+    let Some(width) = self.get_available_width(level, column + reserved) else {
+      return message.into();
+    };
+
+    let indentation = format!("\n{}", " ".repeat(column));
+
+    match self.wrap {
+      ConsoleWrap::Off => message.into(),
+      ConsoleWrap::Truncate => {
+        let mut lines = message.lines();
+        let first_line = lines.next().unwrap_or_default();
+
+        match lines.next().is_some() {
+          true => truncate(&format!("{} …", first_line), width),
+          false => truncate(first_line, width),
+        }
+      }
+      ConsoleWrap::Wrap => message
+        .lines()
+        .flat_map(|l| wrap(l, width))
+        .collect::<Vec<_>>()
+        .join(&indentation),
+    }
+  }
+
+  /// Lays out the error that goes below the message, including the line break.
+  pub fn format_error(&self, level: LogLevel, column: usize, error: &str) -> String {
+    let error = match self.compact_errors {
+      true => compact_error(error),
+      false => error.trim_end().into(),
+    };
+
+    let Some(width) = self.get_available_width(level, column) else {
+      return format!("\n{}", error);
+    };
+
+    let indentation = " ".repeat(column);
+
+    let lines = match self.wrap {
+      ConsoleWrap::Off => return format!("\n{}", error),
+      ConsoleWrap::Truncate => error.lines().map(|l| truncate(l, width)).collect(),
+      ConsoleWrap::Wrap => error
+        .lines()
+        .flat_map(|l| wrap(l, width))
+        .collect::<Vec<_>>(),
+    };
+
+    lines
+      .iter()
+      .map(|l| format!("\n{}{}", indentation, l))
+      .collect()
+  }
+
+  fn get_available_width(&self, level: LogLevel, used: usize) -> Option<usize> {
+    if self.wrap == ConsoleWrap::Off {
+      return None;
+    }
+
+    let width = self.width.or_else(|| get_terminal_width(level))?;
+
+    Some(width.saturating_sub(used).max(MIN_WIDTH))
+  }
+}
+
+/// Joins the error tree into a single line, like `error: a (at src/main.rs:1:1) → error: b`,
+/// leaving the backtraces out.
+pub fn compact_error(error: &str) -> String {
+  let mut result: Vec<String> = Vec::new();
+
+  for line in error.lines() {
+    let line = line.trim_start_matches([' ', '│']);
+    let is_heading = line.starts_with("╰─▶") || line.starts_with("├─▶");
+
+    let line = line.trim_start_matches(['╰', '─', '▶', '├', '╴']).trim();
+
+    // The backtrace frames don't fit in a single line
+    if line.is_empty() || line.starts_with(crate::error::FRAME_MARK) {
+      continue;
+    }
+
+    // The lines below a heading, like its location or its notes, go along with it
+    match (is_heading, result.last_mut()) {
+      (false, Some(last)) => last.push_str(&format!(" ({})", line)),
+      _ => result.push(line.into()),
+    }
+  }
+
+  result.join(" → ")
+}
+
+// The errors go to stderr and the rest to stdout, so each one is measured on its own
+#[cfg_attr(not(feature = "logger-console-width"), allow(unused_variables))]
+fn get_terminal_width(level: LogLevel) -> Option<usize> {
+  #[cfg(feature = "logger-console-width")]
+  {
+    let size = match level {
+      LogLevel::Error => terminal_size_of(std::io::stderr()),
+      _ => terminal_size_of(std::io::stdout()),
+    };
+
+    if let Some((Width(width), _)) = size {
+      return Some(width as usize);
+    }
+  }
+
+  env::var("COLUMNS").ok().and_then(|c| c.parse().ok())
+}
+
+fn truncate(line: &str, width: usize) -> String {
+  match line.chars().count() > width {
+    true => format!("{}…", line.trunc(width - 1)),
+    false => line.into(),
+  }
+}
+
+// The indentation of the line, like the one of the error tree, is kept in every piece
+fn wrap(line: &str, width: usize) -> Vec<String> {
+  let content = line.trim_start();
+  let indentation = &line[..line.len() - content.len()];
+  let width = width.saturating_sub(indentation.chars().count()).max(1);
+
+  let mut result = Vec::new();
+  let mut current = String::new();
+
+  for word in content.split(' ') {
+    let mut word = word.to_string();
+
+    // The words that don't fit in a line on their own are split
+    while word.chars().count() > width {
+      if !current.is_empty() {
+        result.push(std::mem::take(&mut current));
+      }
+
+      let head = word.clone().trunc(width);
+      word = word.chars().skip(width).collect();
+      result.push(head);
+    }
+
+    let current_len = current.chars().count();
+
+    match current_len {
+      0 => current = word,
+      _ if current_len + 1 + word.chars().count() <= width => {
+        current.push(' ');
+        current.push_str(&word);
+      }
+      _ => result.push(std::mem::replace(&mut current, word)),
+    }
+  }
+
+  result.push(current);
+
+  result
+    .into_iter()
+    .map(|l| format!("{}{}", indentation, l))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn get_layout(wrap: ConsoleWrap, width: usize) -> ConsoleLayout {
+    ConsoleLayout {
+      wrap,
+      width: Some(width),
+      compact_errors: false,
+    }
+  }
+
+  #[test]
+  fn wrap_breaks_at_the_words() {
+    assert_eq!(wrap("the quick brown fox", 10), ["the quick", "brown fox"]);
+  }
+
+  #[test]
+  fn wrap_splits_the_long_words_and_keeps_the_indentation() {
+    assert_eq!(wrap("  abcdefghijkl", 7), ["  abcde", "  fghij", "  kl"]);
+  }
+
+  #[test]
+  fn truncate_counts_the_characters() {
+    assert_eq!(truncate("hello world", 8), "hello w…");
+    assert_eq!(truncate("ééééé", 3), "éé…");
+    assert_eq!(truncate("short", 8), "short");
+  }
+
+  #[test]
+  fn format_message_aligns_the_wrapped_lines() {
+    let layout = get_layout(ConsoleWrap::Wrap, 24);
+
+    assert_eq!(
+      layout.format_message(LogLevel::Info, 4, 0, "aaaa bbbb cccc dddd eeee"),
+      "aaaa bbbb cccc dddd\n    eeee"
+    );
+  }
+
+  #[test]
+  fn format_message_truncates_to_the_first_line() {
+    let layout = get_layout(ConsoleWrap::Truncate, 30);
+
+    assert_eq!(
+      layout.format_message(LogLevel::Info, 0, 0, "first line\nsecond line"),
+      "first line …"
+    );
+  }
+
+  #[test]
+  fn format_message_keeps_a_minimum_width() {
+    let layout = get_layout(ConsoleWrap::Truncate, 10);
+    let message = "a".repeat(30);
+
+    assert_eq!(
+      layout.format_message(LogLevel::Info, 5, 0, &message),
+      format!("{}…", "a".repeat(MIN_WIDTH - 1))
+    );
+  }
+
+  #[test]
+  fn format_error_indents_every_line() {
+    let layout = get_layout(ConsoleWrap::Truncate, 30);
+
+    assert_eq!(
+      layout.format_error(LogLevel::Error, 2, "error: boom\n╰╴at src/main.rs:1:1\n"),
+      "\n  error: boom\n  ╰╴at src/main.rs:1:1"
+    );
+    assert_eq!(
+      get_layout(ConsoleWrap::Off, 30).format_error(LogLevel::Error, 2, "error: boom"),
+      "\nerror: boom"
+    );
+  }
+
+  #[test]
+  fn compact_error_joins_the_tree() {
+    let error = [
+      "error: couldn't start",
+      "├╴at src/main.rs:1:1",
+      "│   ↳ main at src/main.rs:1:1",
+      "╰─▶ error (IO): file missing",
+      "    ╰╴note: check the path",
+    ]
+    .join("\n");
+
+    assert_eq!(
+      compact_error(&error),
+      "error: couldn't start (at src/main.rs:1:1) → error (IO): file missing (note: check the path)"
+    );
+  }
+
+  #[test]
+  fn compact_error_is_used_by_the_layout() {
+    let layout = ConsoleLayout {
+      compact_errors: true,
+      ..Default::default()
+    };
+
+    assert_eq!(
+      layout.format_error(LogLevel::Error, 0, "error: a\n╰─▶ error: b"),
+      "\nerror: a → error: b"
+    );
+  }
+}
@@ -18,6 +18,16 @@ impl LogFields {
       error: None,
     }
   }
+
+  /// Whether the field is part of the log, the fields of the records bridged from the 'log' crate
+  /// included.
+  #[cfg_attr(not(feature = "otel"), allow(dead_code))]
+  pub fn is_log_field(name: &str) -> bool {
+    matches!(
+      name,
+      "message" | "msg" | "error" | "err" | "category" | "cat" | "stopwatch" | "sw"
+    ) || name.starts_with("log.")
+  }
 }
 
 impl tracing::field::Visit for LogFields {
@@ -1,29 +1,55 @@
 // Copyright (c) Toolbi Software. All rights reserved.
 // Check the README file in the project root for more information.
 
-// TODO: Handle flushing and stopping the logger
-
 use std::{
   fs::{self, File, OpenOptions},
   path::{Path, PathBuf},
   sync::{Arc, Mutex},
-  time::Duration,
 };
 
 use csv::{Writer, WriterBuilder};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use super::LogLevel;
+#[cfg(feature = "logger-audit")]
+use super::audit;
+use super::{
+  worker::{Worker, Workers},
+  LogLevel,
+};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FileLogger {
   path: PathBuf,
   buffer: Arc<Mutex<Vec<FileLog>>>,
-  // writer: Option<Arc<Mutex<Writer<File>>>>,
+  #[cfg(feature = "logger-audit")]
+  audit: Option<audit::AuditRules>,
+  writer: Arc<Mutex<Option<FileWriter>>>,
+}
+
+/// Writes logs into daily rotated CSV files inside a directory.
+#[derive(Debug)]
+pub struct FileWriter {
+  dir_path: PathBuf,
+  timestamp: String,
+  // There is no plain file when every log is audited
+  writer: Option<Writer<File>>,
+  // The file was created before the 'sample_rate' column, so its rows are written without it
+  legacy: bool,
+  #[cfg(feature = "logger-audit")]
+  audit: Option<AuditWriter>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Writes the audited logs into their own hash chained files.
+#[cfg(feature = "logger-audit")]
+#[derive(Debug)]
+struct AuditWriter {
+  rules: audit::AuditRules,
+  writer: Writer<File>,
+  chain: audit::Chain,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileLog {
   pub timestamp: i64,
   pub level: LogLevel,
@@ -31,6 +57,20 @@ pub struct FileLog {
   pub message: Option<String>,
   pub stopwatch: Option<String>,
   pub error: Option<String>,
+  /// The fraction of the logs like this one that were kept, to extrapolate the real counts.
+  #[serde(default)]
+  pub sample_rate: Option<f64>,
+}
+
+/// The columns of the log files before `sample_rate` was added.
+#[derive(Debug, Serialize)]
+struct LegacyFileLog {
+  timestamp: i64,
+  level: LogLevel,
+  category: Option<String>,
+  message: Option<String>,
+  stopwatch: Option<String>,
+  error: Option<String>,
 }
 
 #[derive(Debug, Error)]
@@ -41,91 +81,209 @@ pub enum Error {
   OpenFile(std::io::Error),
   #[error("couldn't serialize the log")]
   Serialize(csv::Error),
+  #[error("couldn't flush the log file")]
+  Flush(std::io::Error),
+  #[cfg(feature = "logger-audit")]
+  #[error("couldn't chain the audit log")]
+  Audit(audit::Error),
 }
 
 impl FileLogger {
-  pub fn new(path: &Path, buffer: Arc<Mutex<Vec<FileLog>>>) -> Self {
-    let mut dir_path = std::env::current_exe()
-      .unwrap()
-      .parent()
-      .unwrap()
-      .to_path_buf();
-    dir_path.push(path);
-    // FIXME: Handle the folder not existing
-    dir_path = fs::canonicalize(dir_path).unwrap();
+  /// With the audit enabled, the logs it selects are hash chained and, if there is a key, the
+  /// files are sealed.
+  pub fn new(config: &super::FileLogger) -> Self {
+    let dir_path = get_dir_path(Path::new(&config.path));
+    // The directory is created along with the first file
+    let dir_path = fs::canonicalize(&dir_path).unwrap_or(dir_path);
 
     Self {
       path: dir_path,
-      buffer,
-      // writer: None,
+      buffer: config.buffer.clone(),
+      #[cfg(feature = "logger-audit")]
+      audit: config.audit.then(|| audit::AuditRules {
+        key: config.audit_key.clone(),
+        categories: config.audit_categories.clone(),
+      }),
+      writer: config.writer.clone(),
     }
   }
 
-  // TODO: Do this better
-  pub fn init(self) {
-    async_std::task::spawn(async move { Self::init_(self).unwrap() });
+  /// Creates the log file right away, then writes the logs every second.
+  pub fn init(self, workers: &Workers) -> Result<(), Error> {
+    self.flush()?;
+    workers.spawn(self);
+
+    Ok(())
   }
 
-  pub async fn init_async(self) -> Result<(), Error> {
-    async_std::task::spawn(async move { Self::init_(self) }).await
+  /// Writes the buffered logs right away, the writer is shared with the background task.
+  pub fn flush(&self) -> Result<(), Error> {
+    self.write_buffer(&mut self.writer.lock().unwrap())
   }
 
-  fn init_(self) -> Result<(), Error> {
-    Self::create_dir(&self.path)?;
+  /// Like [`FileLogger::flush`], but nothing is written if the writer is already in use.
+  pub fn try_flush(&self) -> Result<(), Error> {
+    match self.writer.try_lock() {
+      Ok(mut writer) => self.write_buffer(&mut writer),
+      Err(_) => Ok(()),
+    }
+  }
 
-    let mut timestamp = chrono::Utc::now().format("%Y%m%d").to_string();
-    let mut path = Self::get_file_path(&self.path, &timestamp);
-    let file = Arc::new(Mutex::new(Self::create_writer(&path)?));
+  fn write_buffer(&self, writer: &mut Option<FileWriter>) -> Result<(), Error> {
+    let writer = match writer {
+      Some(writer) => writer,
+      None => writer.insert(self.new_writer()?),
+    };
 
-    let sleep_dur = Duration::from_secs(1);
+    let logs = {
+      let mut buffer = self.buffer.lock().unwrap();
 
-    loop {
-      std::thread::sleep(sleep_dur);
+      std::mem::take(&mut *buffer)
+    };
 
-      let logs = {
-        let mut buffer = self.buffer.lock().unwrap();
+    for log in logs {
+      writer.write(log)?;
+    }
 
-        std::mem::take(&mut *buffer)
-      };
+    writer.flush()
+  }
 
-      for log in logs {
-        let log_timestamp = {
-          let date_time = chrono::DateTime::from_timestamp_millis(log.timestamp).unwrap();
+  fn new_writer(&self) -> Result<FileWriter, Error> {
+    #[cfg(feature = "logger-audit")]
+    if let Some(rules) = &self.audit {
+      return FileWriter::new_audit(&self.path, rules.clone());
+    }
 
-          date_time.format("%Y%m%d").to_string()
-        };
+    FileWriter::new(&self.path)
+  }
+}
 
-        if log_timestamp != timestamp {
-          file.lock().unwrap().flush().unwrap(); // TODO: Handle this posible error
+impl Worker for FileLogger {
+  fn work(&self) {
+    if let Err(error) = self.flush() {
+      super::report_error(error);
+    }
+  }
+}
 
-          timestamp = log_timestamp;
-          path = Self::get_file_path(&self.path, &timestamp);
+impl FileWriter {
+  pub fn new(dir_path: &Path) -> Result<Self, Error> {
+    Self::create_dir(dir_path)?;
 
-          let get_file = Self::create_writer(&path)?;
-          let mut file_mutex = file.lock().unwrap();
-          *file_mutex = get_file;
+    let timestamp = chrono::Utc::now().format("%Y%m%d").to_string();
+    let path = Self::get_file_path(dir_path, &timestamp);
+
+    Ok(Self {
+      dir_path: dir_path.to_path_buf(),
+      writer: Some(Self::create_writer(&path)?),
+      legacy: Self::is_legacy(&path),
+      timestamp,
+      #[cfg(feature = "logger-audit")]
+      audit: None,
+    })
+  }
+
+  /// Writes hash chained logs, resuming the chain of the files already in the directory.
+  ///
+  /// The logs of the categories that aren't audited go to the plain files.
+  #[cfg(feature = "logger-audit")]
+  pub fn new_audit(dir_path: &Path, rules: audit::AuditRules) -> Result<Self, Error> {
+    let mut file_writer = match rules.categories.is_empty() {
+      true => {
+        Self::create_dir(dir_path)?;
+
+        Self {
+          dir_path: dir_path.to_path_buf(),
+          timestamp: chrono::Utc::now().format("%Y%m%d").to_string(),
+          writer: None,
+          legacy: false,
+          audit: None,
         }
+      }
+      false => Self::new(dir_path)?,
+    };
+
+    let path = Self::get_audit_file_path(dir_path, &file_writer.timestamp);
+    let chain = audit::Chain::resume(dir_path, &path, rules.key.clone()).map_err(Error::Audit)?;
+
+    file_writer.audit = Some(AuditWriter {
+      rules,
+      writer: Self::create_writer(&path)?,
+      chain,
+    });
+
+    Ok(file_writer)
+  }
+
+  /// Writes the log, switching to another file if it belongs to a different day.
+  pub fn write(&mut self, log: FileLog) -> Result<(), Error> {
+    let log_timestamp = {
+      let date_time = chrono::DateTime::from_timestamp_millis(log.timestamp).unwrap();
+
+      date_time.format("%Y%m%d").to_string()
+    };
 
-        file
-          .lock()
-          .unwrap()
-          .serialize(log)
-          .map_err(Error::Serialize)?;
+    if log_timestamp != self.timestamp {
+      self.flush()?;
+
+      #[cfg(feature = "logger-audit")]
+      if let Some(audit) = &mut self.audit {
+        let path = Self::get_audit_file_path(&self.dir_path, &self.timestamp);
+        audit.chain.seal(&path).map_err(Error::Audit)?;
+
+        let path = Self::get_audit_file_path(&self.dir_path, &log_timestamp);
+        audit.chain.start_file(&path);
+        audit.writer = Self::create_writer(&path)?;
+      }
+
+      if self.writer.is_some() {
+        let path = Self::get_file_path(&self.dir_path, &log_timestamp);
+
+        self.legacy = Self::is_legacy(&path);
+        self.writer = Some(Self::create_writer(&path)?);
       }
 
-      file.lock().unwrap().flush().unwrap(); // TODO: Handle this posible error
+      self.timestamp = log_timestamp;
+    }
+
+    #[cfg(feature = "logger-audit")]
+    if let Some(audit) = self.audit.as_mut().filter(|a| a.rules.is_audited(&log)) {
+      let record = audit.chain.link(log).map_err(Error::Audit)?;
+      return audit.writer.serialize(record).map_err(Error::Serialize);
+    }
+
+    match &mut self.writer {
+      Some(writer) if self.legacy => writer
+        .serialize(LegacyFileLog::from(log))
+        .map_err(Error::Serialize),
+      Some(writer) => writer.serialize(log).map_err(Error::Serialize),
+      None => Ok(()),
     }
   }
 
+  /// Flushes the files, then moves the head of the audit chain to the last record written.
+  pub fn flush(&mut self) -> Result<(), Error> {
+    if let Some(writer) = &mut self.writer {
+      writer.flush().map_err(Error::Flush)?;
+    }
+
+    #[cfg(feature = "logger-audit")]
+    if let Some(audit) = &mut self.audit {
+      audit.writer.flush().map_err(Error::Flush)?;
+      audit.chain.save_head().map_err(Error::Audit)?;
+    }
+
+    Ok(())
+  }
+
   fn create_dir(path: &Path) -> Result<(), Error> {
     fs::create_dir_all(path).map_err(Error::CreateDir)
   }
 
   fn create_writer(path: &Path) -> Result<Writer<File>, Error> {
-    let file_exists = path.metadata().is_ok();
+    let file_exists = path.metadata().is_ok_and(|m| m.len() > 0);
     let file = OpenOptions::new()
       .create(true)
-      .write(true)
       .append(true)
       .open(path)
       .map_err(Error::OpenFile)?;
@@ -137,7 +295,131 @@ impl FileLogger {
     )
   }
 
+  /// Whether the file was written before the 'sample_rate' column was added.
+  ///
+  /// The rest of its rows keep its columns, so it can still be read until the next day.
+  fn is_legacy(path: &Path) -> bool {
+    let Ok(mut reader) = csv::Reader::from_path(path) else {
+      return false;
+    };
+
+    match reader.headers() {
+      Ok(headers) => !headers.is_empty() && !headers.iter().any(|h| h == "sample_rate"),
+      Err(_) => false,
+    }
+  }
+
   fn get_file_path(dir_path: &Path, timestamp: &str) -> PathBuf {
     dir_path.join(format!("{}-log.csv", timestamp))
   }
+
+  #[cfg(feature = "logger-audit")]
+  fn get_audit_file_path(dir_path: &Path, timestamp: &str) -> PathBuf {
+    dir_path.join(format!("{}{}", timestamp, audit::FILE_SUFFIX))
+  }
+}
+
+impl From<FileLog> for LegacyFileLog {
+  fn from(value: FileLog) -> Self {
+    Self {
+      timestamp: value.timestamp,
+      level: value.level,
+      category: value.category,
+      message: value.message,
+      stopwatch: value.stopwatch,
+      error: value.error,
+    }
+  }
+}
+
+/// Resolves the path relative to the directory of the executable.
+pub fn get_dir_path(path: &Path) -> PathBuf {
+  let mut dir_path = std::env::current_exe()
+    .unwrap()
+    .parent()
+    .unwrap()
+    .to_path_buf();
+  dir_path.push(path);
+
+  dir_path
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::logger::{test_support, test_support::TempDir};
+
+  fn get_log(message: &str, sample_rate: Option<f64>) -> FileLog {
+    FileLog {
+      sample_rate,
+      ..test_support::get_log(message)
+    }
+  }
+
+  fn read_rows(path: &Path) -> (Vec<String>, Vec<Vec<String>>) {
+    let mut reader = csv::Reader::from_path(path).unwrap();
+    let headers = reader.headers().unwrap().iter().map(String::from).collect();
+    let rows = reader
+      .records()
+      .map(|r| r.unwrap().iter().map(String::from).collect())
+      .collect();
+
+    (headers, rows)
+  }
+
+  fn get_today_path(dir_path: &Path) -> PathBuf {
+    let timestamp = chrono::Utc::now().format("%Y%m%d").to_string();
+    FileWriter::get_file_path(dir_path, &timestamp)
+  }
+
+  #[test]
+  fn new_files_have_the_sample_rate() {
+    let dir_path = TempDir::new("file-new");
+
+    let mut writer = FileWriter::new(&dir_path).unwrap();
+    writer.write(get_log("sampled", Some(0.25))).unwrap();
+    writer.flush().unwrap();
+
+    let (headers, rows) = read_rows(&get_today_path(&dir_path));
+    assert_eq!(headers.last().unwrap(), "sample_rate");
+    assert_eq!(rows[0].last().unwrap(), "0.25");
+  }
+
+  #[test]
+  fn legacy_files_keep_their_columns() {
+    let dir_path = TempDir::new("file-legacy");
+    let path = get_today_path(&dir_path);
+    fs::write(
+      &path,
+      "timestamp,level,category,message,stopwatch,error\n1,info,,before the upgrade,,\n",
+    )
+    .unwrap();
+
+    let mut writer = FileWriter::new(&dir_path).unwrap();
+    writer
+      .write(get_log("after the upgrade", Some(0.5)))
+      .unwrap();
+    writer.flush().unwrap();
+
+    let (headers, rows) = read_rows(&path);
+    assert_eq!(headers.len(), 6);
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[1].len(), 6);
+    assert_eq!(rows[1][3], "after the upgrade");
+  }
+
+  #[test]
+  fn empty_files_get_the_header() {
+    let dir_path = TempDir::new("file-empty");
+    let path = get_today_path(&dir_path);
+    fs::write(&path, "").unwrap();
+
+    let mut writer = FileWriter::new(&dir_path).unwrap();
+    writer.write(get_log("first", None)).unwrap();
+    writer.flush().unwrap();
+
+    let (headers, rows) = read_rows(&path);
+    assert_eq!(headers.len(), 7);
+    assert_eq!(rows[0][3], "first");
+  }
 }
@@ -0,0 +1,140 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use std::{os::unix::net::UnixDatagram, path::Path};
+
+use super::{get_app_name, Error, FileLog};
+
+#[derive(Debug)]
+pub struct JournaldSink {
+  socket: UnixDatagram,
+  identifier: String,
+}
+
+impl JournaldSink {
+  pub fn connect(path: &Path) -> Result<Self, Error> {
+    let socket = UnixDatagram::unbound().map_err(Error::Journald)?;
+    socket.connect(path).map_err(Error::Journald)?;
+
+    Ok(Self {
+      socket,
+      identifier: get_app_name(),
+    })
+  }
+
+  pub fn log(&self, log: &FileLog) {
+    let payload = self.format(log);
+
+    // A logger has nowhere to report its own failures
+    let _ = self.socket.send(&payload);
+  }
+
+  fn format(&self, log: &FileLog) -> Vec<u8> {
+    let mut payload = Vec::new();
+    let priority = log.level.to_syslog_severity().unwrap_or(7).to_string();
+
+    push_field(&mut payload, "PRIORITY", &priority);
+    push_field(&mut payload, "SYSLOG_IDENTIFIER", &self.identifier);
+    push_field(
+      &mut payload,
+      "MESSAGE",
+      log.message.as_deref().unwrap_or(""),
+    );
+
+    if let Some(category) = &log.category {
+      push_field(&mut payload, "CATEGORY", category);
+    }
+    if let Some(error) = &log.error {
+      push_field(&mut payload, "ERROR", error);
+    }
+    if let Some(stopwatch) = &log.stopwatch {
+      push_field(&mut payload, "STOPWATCH", stopwatch);
+    }
+
+    payload
+  }
+}
+
+/// Values containing a newline use the length-prefixed binary form of the native protocol.
+fn push_field(payload: &mut Vec<u8>, key: &str, value: &str) {
+  payload.extend_from_slice(key.as_bytes());
+
+  match value.contains('\n') {
+    true => {
+      payload.push(b'\n');
+      payload.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    }
+    false => payload.push(b'='),
+  }
+
+  payload.extend_from_slice(value.as_bytes());
+  payload.push(b'\n');
+}
+
+#[cfg(test)]
+mod tests {
+  use std::os::unix::net::UnixDatagram;
+
+  use super::*;
+  use crate::logger::{
+    test_support::{get_log, TempDir},
+    LogLevel,
+  };
+
+  fn receive(name: &str, log: &FileLog) -> Vec<u8> {
+    let dir_path = TempDir::new(&format!("journald-{}", name));
+    let path = dir_path.join("journald.sock");
+    let server = UnixDatagram::bind(&path).unwrap();
+
+    let sink = JournaldSink::connect(&path).unwrap();
+    sink.log(log);
+
+    let mut buffer = [0; 1024];
+    let len = server.recv(&mut buffer).unwrap();
+
+    buffer[..len].to_vec()
+  }
+
+  #[test]
+  fn sends_the_fields_as_key_value_lines() {
+    let payload = receive(
+      "fields",
+      &FileLog {
+        category: Some("HTTP".into()),
+        stopwatch: Some("12.5ms".into()),
+        ..get_log("request served")
+      },
+    );
+
+    let expected = format!(
+      "PRIORITY=6\nSYSLOG_IDENTIFIER={}\nMESSAGE=request served\nCATEGORY=HTTP\nSTOPWATCH=12.5ms\n",
+      get_app_name()
+    );
+    assert_eq!(String::from_utf8(payload).unwrap(), expected);
+  }
+
+  #[test]
+  fn sends_multi_line_values_length_prefixed() {
+    let payload = receive(
+      "multi-line",
+      &FileLog {
+        level: LogLevel::Error,
+        message: None,
+        error: Some("error: a\n╰─▶ error: b".into()),
+        ..get_log("")
+      },
+    );
+
+    let error = "error: a\n╰─▶ error: b";
+    let mut expected = format!(
+      "PRIORITY=3\nSYSLOG_IDENTIFIER={}\nMESSAGE=\nERROR\n",
+      get_app_name()
+    )
+    .into_bytes();
+    expected.extend_from_slice(&(error.len() as u64).to_le_bytes());
+    expected.extend_from_slice(error.as_bytes());
+    expected.push(b'\n');
+
+    assert_eq!(payload, expected);
+  }
+}
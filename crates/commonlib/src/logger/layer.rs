@@ -1,20 +1,75 @@
 // Copyright (c) Toolbi Software. All rights reserved.
 // Check the README file in the project root for more information.
 
-use std::{collections::HashMap, env};
+use std::{
+  collections::HashMap,
+  env,
+  sync::{Arc, Mutex},
+};
 
 use tracing::Subscriber;
+#[cfg(feature = "log-bridge")]
+use tracing_log::NormalizeEvent;
 use tracing_subscriber::registry::LookupSpan;
 
 use crate::str::PadLen;
 
-use super::{fields::LogFields, FileLog, FileLogger, LogLevel};
+#[cfg(feature = "logger-redaction")]
+use super::redactor::Redactor;
+#[cfg(feature = "logger-network")]
+use super::NetworkLogger;
+use super::{
+  console::ConsoleLayout, fields::LogFields, limiter::Limiter, metrics::Metrics, ring::RingBuffer,
+  sampler::Sampler, worker::Worker, FileLog, FileLogger, LogLevel,
+};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Layer {
   pub level: LogLevel,
   pub module_filters: HashMap<String, LogLevel>,
   pub file_logger: FileLogger,
+  #[cfg(feature = "logger-network")]
+  pub network_logger: NetworkLogger,
+  #[cfg(unix)]
+  pub syslog_logger: Option<Arc<super::syslog::SyslogSink>>,
+  #[cfg(unix)]
+  pub journald_logger: Option<Arc<super::journald::JournaldSink>>,
+  #[cfg(feature = "otel")]
+  pub otel_exporter: super::OtelExporter,
+  pub capture: Option<Arc<Mutex<Vec<FileLog>>>>,
+  pub limiter: Limiter,
+  pub sampler: Sampler,
+  #[cfg(feature = "logger-redaction")]
+  pub redactor: Redactor,
+  pub ring_buffer: RingBuffer,
+  pub metrics: Metrics,
+  pub console_layout: ConsoleLayout,
+}
+
+/// Reports the logs the limiter dropped without waiting for their callsites to log again.
+#[derive(Debug)]
+pub struct SummaryWorker(pub Layer);
+
+/// What the sinks need to know about the event besides the log itself.
+#[cfg_attr(not(feature = "otel"), allow(dead_code))]
+struct EventContext<'a> {
+  target: &'a str,
+  #[cfg(feature = "otel")]
+  span: Option<([u8; 16], [u8; 8])>,
+  /// The fields of the event that aren't part of the log.
+  #[cfg(feature = "otel")]
+  attributes: &'a [(String, String)],
+}
+
+/// The module the filters and the sampler look up.
+///
+/// The records bridged from the 'log' crate are filtered by their target, which is the module
+/// unless it's set explicitly, like the events are by their module.
+fn get_module<'a>(metadata: &'a tracing::Metadata<'_>, is_log: bool) -> &'a str {
+  match is_log {
+    true => metadata.target(),
+    false => metadata.module_path().unwrap_or(metadata.target()),
+  }
 }
 
 fn log_to_console(level: LogLevel, string: &str) {
@@ -35,16 +90,280 @@ where
     metadata: &tracing::Metadata<'_>,
     _ctx: tracing_subscriber::layer::Context<'_, S>,
   ) -> bool {
+    // The records bridged from the 'log' crate are checked before they're normalized
+    let is_log = cfg!(feature = "log-bridge") && metadata.fields().field("log.target").is_some();
+
+    // The ring buffer can keep the logs below the level as well
+    self.is_level_enabled(metadata, is_log)
+      || self
+        .ring_buffer
+        .is_level_enabled(LogLevel::from(metadata.level()))
+  }
+
+  #[cfg(feature = "otel")]
+  fn on_new_span(
+    &self,
+    attrs: &tracing::span::Attributes<'_>,
+    id: &tracing::span::Id,
+    ctx: tracing_subscriber::layer::Context<'_, S>,
+  ) {
+    use super::otel::{OtelSpan, SpanAttributes};
+
+    // The span may only be enabled for the ring buffer
+    if !self.otel_exporter.enable || !self.is_level_enabled(attrs.metadata(), false) {
+      return;
+    }
+
+    let Some(span) = ctx.span(id) else {
+      return;
+    };
+
+    let parent = span.parent().and_then(|p| {
+      p.extensions()
+        .get::<OtelSpan>()
+        .map(|s| (s.trace_id, s.span_id))
+    });
+
+    let metadata = attrs.metadata();
+    let mut otel_span = OtelSpan::new(
+      metadata.name(),
+      metadata.target(),
+      parent.map(|p| p.0),
+      parent.map(|p| p.1),
+    );
+    attrs.record(&mut SpanAttributes(&mut otel_span.attributes));
+
+    span.extensions_mut().insert(otel_span);
+  }
+
+  #[cfg(feature = "otel")]
+  fn on_record(
+    &self,
+    id: &tracing::span::Id,
+    values: &tracing::span::Record<'_>,
+    ctx: tracing_subscriber::layer::Context<'_, S>,
+  ) {
+    use super::otel::{OtelSpan, SpanAttributes};
+
+    if let Some(span) = ctx.span(id) {
+      if let Some(otel_span) = span.extensions_mut().get_mut::<OtelSpan>() {
+        values.record(&mut SpanAttributes(&mut otel_span.attributes));
+      }
+    }
+  }
+
+  #[cfg(feature = "otel")]
+  fn on_close(&self, id: tracing::span::Id, ctx: tracing_subscriber::layer::Context<'_, S>) {
+    use super::otel::{now_nanos, OtelSpan};
+
+    if let Some(span) = ctx
+      .span(&id)
+      .filter(|s| self.is_level_enabled(s.metadata(), false))
+    {
+      if let Some(mut otel_span) = span.extensions_mut().remove::<OtelSpan>() {
+        otel_span.end_time = now_nanos();
+
+        let mut buffer = self.otel_exporter.spans.lock().unwrap();
+        buffer.push(otel_span)
+      }
+    }
+  }
+
+  fn on_event(&self, event: &tracing::Event<'_>, ctx: tracing_subscriber::layer::Context<'_, S>) {
+    #[cfg(feature = "log-bridge")]
+    let (normalized_metadata, is_log) = (event.normalized_metadata(), event.is_log());
+    #[cfg(not(feature = "log-bridge"))]
+    let (normalized_metadata, is_log) = (None::<tracing::Metadata>, false);
+    let metadata = normalized_metadata.as_ref().unwrap_or(event.metadata());
+
+    let mut is_emitted = self.is_level_enabled(metadata, is_log);
+    let is_kept = self
+      .ring_buffer
+      .is_level_enabled(LogLevel::from(metadata.level()));
+    let mut sample_rate = None;
+
+    if is_emitted && self.sampler.is_enabled() {
+      let module = get_module(metadata, is_log);
+
+      match self
+        .sampler
+        .sample(module, LogLevel::from(metadata.level()))
+      {
+        Some(rate) => sample_rate = (rate < 1.0).then_some(rate),
+        None => {
+          self.metrics.add_sampled_out();
+          is_emitted = false
+        }
+      }
+    }
+
+    if !is_emitted && !is_kept {
+      return ctx.event(event);
+    }
+
+    let fields = {
+      let mut result = LogFields::new();
+      event.record(&mut result);
+      result
+    };
+
+    let mut file_log = FileLog {
+      timestamp: chrono::Utc::now().timestamp_millis(),
+      level: LogLevel::from(metadata.level()),
+      category: fields.category,
+      message: fields.message,
+      stopwatch: fields.stopwatch,
+      error: fields.error,
+      sample_rate,
+    };
+
+    // Before anything else sees the log, the limiter included
+    self.redact(&mut file_log);
+
+    if is_kept {
+      self.ring_buffer.push(file_log.clone());
+    }
+
+    if !is_emitted {
+      return ctx.event(event);
+    }
+
+    let log_level = file_log.level;
+
+    let (summaries, file_log) = match self.limiter.is_enabled() {
+      true => self.limiter.check(metadata, is_log, file_log),
+      false => (Vec::new(), Some(file_log)),
+    };
+
+    // The summaries of the dropped logs aren't counted, the dropped logs already are
+    match &file_log {
+      Some(file_log) => self.metrics.add_emitted(file_log),
+      None => self.metrics.add_dropped(),
+    }
+
+    // Only the errors that are actually logged, the dropped ones would dump the same logs again
+    if self.ring_buffer.enable && log_level == LogLevel::Error && file_log.is_some() {
+      let _ = self.ring_buffer.dump();
+    }
+
+    if let Some(capture) = &self.capture {
+      let mut buffer = capture.lock().unwrap();
+      buffer.extend(summaries.into_iter().chain(file_log));
+
+      return ctx.event(event);
+    }
+
+    #[cfg(feature = "otel")]
+    let attributes = match self.otel_exporter.enable {
+      true => {
+        let mut result = Vec::new();
+        event.record(&mut super::otel::SpanAttributes(&mut result));
+        result.retain(|(name, _)| !LogFields::is_log_field(name));
+        result
+      }
+      false => Vec::new(),
+    };
+
+    let event_context = EventContext {
+      target: metadata.target(),
+      #[cfg(feature = "otel")]
+      span: ctx.event_span(event).and_then(|s| {
+        s.extensions()
+          .get::<super::otel::OtelSpan>()
+          .map(|s| (s.trace_id, s.span_id))
+      }),
+      #[cfg(feature = "otel")]
+      attributes: &attributes,
+    };
+
+    // The fields belong to this event, not to the ones the summaries are about
+    for summary in summaries {
+      let summary_context = EventContext {
+        #[cfg(feature = "otel")]
+        attributes: &[],
+        ..event_context
+      };
+
+      self.emit(summary, &summary_context);
+    }
+
+    if let Some(file_log) = file_log {
+      self.emit(file_log, &event_context);
+    }
+
+    // ! Why?
+    ctx.event(event)
+  }
+}
+
+impl Worker for SummaryWorker {
+  fn work(&self) {
+    let layer = &self.0;
+
+    for (target, file_log) in layer.limiter.take_summaries() {
+      layer.log_direct(file_log, &target);
+    }
+  }
+}
+
+impl Layer {
+  /// Logs the panic past the sampler and the limiter, so it's never dropped.
+  pub(super) fn log_panic(&self, message: String) {
+    let mut file_log = FileLog {
+      timestamp: chrono::Utc::now().timestamp_millis(),
+      level: LogLevel::Error,
+      category: Some("PANIC".into()),
+      message: Some(message),
+      stopwatch: None,
+      error: None,
+      sample_rate: None,
+    };
+
+    self.redact(&mut file_log);
+
+    if self.ring_buffer.enable {
+      self.ring_buffer.push(file_log.clone());
+      let _ = self.ring_buffer.dump();
+    }
+
+    self.metrics.add_emitted(&file_log);
+    self.log_direct(file_log, module_path!());
+  }
+
+  /// Sends a log that doesn't come from an event to the capture or to the sinks.
+  fn log_direct(&self, file_log: FileLog, target: &str) {
+    if let Some(capture) = &self.capture {
+      capture.lock().unwrap().push(file_log);
+      return;
+    }
+
+    let event_context = EventContext {
+      target,
+      #[cfg(feature = "otel")]
+      span: None,
+      #[cfg(feature = "otel")]
+      attributes: &[],
+    };
+
+    self.emit(file_log, &event_context);
+  }
+
+  #[cfg_attr(not(feature = "logger-redaction"), allow(unused_variables))]
+  fn redact(&self, file_log: &mut FileLog) {
+    #[cfg(feature = "logger-redaction")]
+    if self.redactor.is_enabled() {
+      self.redactor.redact(file_log);
+    }
+  }
+
+  fn is_level_enabled(&self, metadata: &tracing::Metadata<'_>, is_log: bool) -> bool {
     let logger_level = {
       let env_level_str = env::var("LOG_LEVEL").unwrap_or("".into());
 
       LogLevel::from_str(&env_level_str).unwrap_or(self.level)
     };
 
-    let module_level = metadata
-      .module_path()
-      .map(|m| self.module_filters.get(m))
-      .unwrap_or(None);
+    let module_level = self.module_filters.get(get_module(metadata, is_log));
 
     let log_level = LogLevel::from(metadata.level());
 
@@ -70,40 +389,45 @@ where
     }
   }
 
-  fn on_event(&self, event: &tracing::Event<'_>, ctx: tracing_subscriber::layer::Context<'_, S>) {
-    let log_level = LogLevel::from(event.metadata().level());
-    let timestamp = chrono::Utc::now();
-    let fields = {
-      let mut result = LogFields::new();
-      event.record(&mut result);
-      result
-    };
+  /// Sends the log to the console and every enabled sink.
+  #[cfg_attr(not(feature = "otel"), allow(unused_variables))]
+  fn emit(&self, file_log: FileLog, event_context: &EventContext) {
+    let log_level = file_log.level;
+    let timestamp = chrono::DateTime::from_timestamp_millis(file_log.timestamp).unwrap();
 
     let str_level = log_level.to_log_str().unwrap();
-    let str_category = fields.category.clone().unwrap_or("".into());
+    let str_category = file_log.category.clone().unwrap_or("".into());
     let str_timestamp = timestamp.format("%Y-%m-%d %H:%M:%S");
-    let str_message = fields.message.clone().unwrap_or("".into());
-    let str_stopwatch = fields.stopwatch.clone().unwrap_or("".into());
-    let str_error = fields
-      .error
-      .as_ref()
-      .map(|e| format!("\n{}", e))
-      .unwrap_or("".into());
+    let str_message = file_log.message.clone().unwrap_or("".into());
+    let str_stopwatch = file_log.stopwatch.clone().unwrap_or("".into());
+
+    // The message column depends on the style of the line
+    let layout = |column: usize| {
+      let reserved = match str_stopwatch.is_empty() {
+        true => 0,
+        false => str_stopwatch.chars().count() + 1,
+      };
+
+      let message = self
+        .console_layout
+        .format_message(log_level, column, reserved, &str_message);
+      let error = file_log
+        .error
+        .as_ref()
+        .map(|e| self.console_layout.format_error(log_level, column, e))
+        .unwrap_or("".into());
+
+      (message, error)
+    };
 
+    #[cfg_attr(not(feature = "colorful-logs"), allow(unused_mut))]
     let mut console_logged = false;
 
     #[cfg(feature = "colorful-logs")]
     {
       use owo_colors::{OwoColorize, Style};
 
-      let terminal_color_support = supports_color::on_cached(supports_color::Stream::Stdout)
-        .map(|a| a.has_256)
-        .unwrap_or(false)
-        && supports_color::on_cached(supports_color::Stream::Stderr)
-          .map(|a| a.has_256)
-          .unwrap_or(false);
-
-      if terminal_color_support {
+      if crate::misc::supports_color() {
         let bg_color = match log_level {
           LogLevel::Error => Some(Style::new().on_red()),
           LogLevel::Warn => Some(Style::new().on_yellow()),
@@ -125,16 +449,17 @@ where
         .unwrap();
 
         let str_level = format!(" | {} ", str_level);
-        let str_category = str_category
-          .is_empty()
-          .then(|| str_category.pad_len(13).style(bg_color).to_string())
-          .unwrap_or(
-            format!("· {} ", str_category.pad_len(10))
-              .style(bg_color)
-              .to_string(),
-          );
+        let str_category = if str_category.is_empty() {
+          str_category.pad_len(13).style(bg_color).to_string()
+        } else {
+          format!("· {} ", str_category.pad_len(10))
+            .style(bg_color)
+            .to_string()
+        };
 
         let str_timestamp = format!(" {} ", str_timestamp);
+        let column = str_level.chars().count() + 13 + str_timestamp.chars().count() + 2;
+        let (str_message, str_error) = layout(column);
         let str_message = str_message
           .is_empty()
           .then(|| str_message.to_string())
@@ -164,12 +489,15 @@ where
 
     if !console_logged {
       let str_level = format!("{} ", str_level);
-      let str_category = str_category
-        .is_empty()
-        .then(|| str_category.pad_len(13))
-        .unwrap_or(format!("· {} ", str_category.pad_len(10)).to_string());
+      let str_category = if str_category.is_empty() {
+        str_category.pad_len(13)
+      } else {
+        format!("· {} ", str_category.pad_len(10))
+      };
 
       let str_timestamp = format!("· {}", str_timestamp);
+      let column = str_level.chars().count() + 13 + str_timestamp.chars().count() + 3;
+      let (str_message, str_error) = layout(column);
       let str_message = str_message
         .is_empty()
         .then(|| str_message.to_string())
@@ -188,21 +516,40 @@ where
       log_to_console(log_level, &console_log);
     }
 
-    if self.file_logger.enable {
-      let file_log = FileLog {
-        timestamp: timestamp.timestamp_millis(),
-        level: log_level,
-        category: fields.category,
-        message: fields.message,
-        stopwatch: fields.stopwatch,
-        error: fields.error,
-      };
+    #[cfg(unix)]
+    {
+      if let Some(syslog_logger) = &self.syslog_logger {
+        syslog_logger.log(&file_log);
+      }
 
+      if let Some(journald_logger) = &self.journald_logger {
+        journald_logger.log(&file_log);
+      }
+    }
+
+    #[cfg(feature = "otel")]
+    if self.otel_exporter.enable {
+      use super::otel::OtelLog;
+
+      let mut buffer = self.otel_exporter.logs.lock().unwrap();
+      buffer.push(OtelLog {
+        log: file_log.clone(),
+        target: event_context.target.into(),
+        trace_id: event_context.span.map(|s| s.0),
+        span_id: event_context.span.map(|s| s.1),
+        attributes: event_context.attributes.to_vec(),
+      })
+    }
+
+    #[cfg(feature = "logger-network")]
+    if self.network_logger.enable {
+      let mut buffer = self.network_logger.buffer.lock().unwrap();
+      buffer.push(file_log.clone())
+    }
+
+    if self.file_logger.enable {
       let mut buffer = self.file_logger.buffer.lock().unwrap();
       buffer.push(file_log)
     }
-
-    // ! Why?
-    ctx.event(event)
   }
 }
@@ -0,0 +1,247 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use std::{
+  collections::HashMap,
+  sync::{Arc, Mutex},
+  time::{Duration, Instant},
+};
+
+use tracing::callsite::Identifier;
+
+use super::FileLog;
+
+/// Lets through at most `count` logs per `interval` from the same callsite.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+  pub count: u32,
+  pub interval: Duration,
+}
+
+/// Drops the logs of noisy callsites and collapses identical consecutive logs of a callsite.
+#[derive(Debug, Clone, Default)]
+pub struct Limiter {
+  pub(super) rate_limit: Option<RateLimit>,
+  pub(super) category_rate_limits: HashMap<String, Option<RateLimit>>,
+  pub(super) dedup: bool,
+  pub(super) category_dedups: HashMap<String, bool>,
+  state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+  windows: HashMap<Callsite, Window>,
+  runs: HashMap<Callsite, Run>,
+}
+
+/// Where the logs come from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Callsite {
+  Event(Identifier),
+  /// The records bridged from the 'log' crate share a callsite per level, so they're told apart
+  /// by where they're logged from.
+  Log {
+    target: String,
+    file: Option<String>,
+    line: Option<u32>,
+  },
+}
+
+/// The logs of a callsite in the current interval.
+#[derive(Debug)]
+struct Window {
+  // Where the logs come from, for the summary
+  location: String,
+  start: Instant,
+  count: u32,
+  dropped: u32,
+  // The summary of the dropped logs takes the level and the category of the last one
+  last_dropped: Option<(String, FileLog)>,
+}
+
+/// The identical consecutive logs of a callsite.
+#[derive(Debug)]
+struct Run {
+  target: String,
+  log: FileLog,
+  repeated: u32,
+}
+
+impl RateLimit {
+  pub fn new(count: u32, interval: Duration) -> Self {
+    Self { count, interval }
+  }
+}
+
+impl Limiter {
+  pub fn is_enabled(&self) -> bool {
+    self.rate_limit.is_some()
+      || self.dedup
+      || self.category_rate_limits.values().any(|l| l.is_some())
+      || self.category_dedups.values().any(|d| *d)
+  }
+
+  /// Returns the summaries of the logs the callsite dropped before, and the log unless it's
+  /// dropped.
+  ///
+  /// The summaries are returned once the callsite logs again, or by [`Limiter::take_summaries`].
+  /// The metadata of the records bridged from the 'log' crate must be normalized.
+  pub fn check(
+    &self,
+    metadata: &tracing::Metadata<'_>,
+    is_log: bool,
+    log: FileLog,
+  ) -> (Vec<FileLog>, Option<FileLog>) {
+    let target = metadata.target();
+    let callsite = match is_log {
+      true => Callsite::Log {
+        target: target.into(),
+        file: metadata.file().map(String::from),
+        line: metadata.line(),
+      },
+      false => Callsite::Event(metadata.callsite()),
+    };
+
+    let mut state = self.state.lock().unwrap();
+    let mut summaries = Vec::new();
+    let dedup = self.get_dedup(&log);
+
+    if dedup {
+      match state.runs.get_mut(&callsite) {
+        Some(run) if is_duplicate(&run.log, &log) => {
+          run.repeated += 1;
+          return (summaries, None);
+        }
+        _ => {}
+      }
+    }
+
+    if let Some(run) = state.runs.remove(&callsite) {
+      if run.repeated > 0 {
+        summaries.push(get_repeated_summary(&run, log.timestamp));
+      }
+    }
+
+    if dedup {
+      state.runs.insert(
+        callsite.clone(),
+        Run {
+          target: target.into(),
+          log: log.clone(),
+          repeated: 0,
+        },
+      );
+    }
+
+    if let Some(rate_limit) = self.get_rate_limit(&log) {
+      let now = Instant::now();
+      let window = state.windows.entry(callsite).or_insert_with(|| Window {
+        location: get_location(metadata),
+        start: now,
+        count: 0,
+        dropped: 0,
+        last_dropped: None,
+      });
+
+      if now.duration_since(window.start) >= rate_limit.interval {
+        if let Some(summary) = get_suppressed_summary(window, log.timestamp) {
+          summaries.push(summary.1);
+        }
+
+        window.start = now;
+        window.count = 0;
+      }
+
+      window.count += 1;
+
+      if window.count > rate_limit.count {
+        window.dropped += 1;
+        window.last_dropped = Some((target.into(), log));
+        return (summaries, None);
+      }
+    }
+
+    (summaries, Some(log))
+  }
+
+  /// Takes the summaries of the logs dropped so far, along with the target of their callsite.
+  ///
+  /// The callsites keep their state, so the logs dropped afterwards get a summary of their own.
+  pub fn take_summaries(&self) -> Vec<(String, FileLog)> {
+    let mut state = self.state.lock().unwrap();
+    let timestamp = chrono::Utc::now().timestamp_millis();
+    let mut result = Vec::new();
+
+    for run in state.runs.values_mut() {
+      if run.repeated > 0 {
+        result.push((run.target.clone(), get_repeated_summary(run, timestamp)));
+        run.repeated = 0;
+      }
+    }
+
+    for window in state.windows.values_mut() {
+      result.extend(get_suppressed_summary(window, timestamp));
+    }
+
+    result
+  }
+
+  fn get_rate_limit(&self, log: &FileLog) -> Option<RateLimit> {
+    log
+      .category
+      .as_ref()
+      .and_then(|c| self.category_rate_limits.get(c).copied())
+      .unwrap_or(self.rate_limit)
+  }
+
+  fn get_dedup(&self, log: &FileLog) -> bool {
+    log
+      .category
+      .as_ref()
+      .and_then(|c| self.category_dedups.get(c).copied())
+      .unwrap_or(self.dedup)
+  }
+}
+
+fn is_duplicate(a: &FileLog, b: &FileLog) -> bool {
+  a.level == b.level && a.category == b.category && a.message == b.message && a.error == b.error
+}
+
+fn get_repeated_summary(run: &Run, timestamp: i64) -> FileLog {
+  summary(
+    &run.log,
+    timestamp,
+    format!("last message repeated {} times", run.repeated),
+  )
+}
+
+/// Reports the logs dropped in the window so far, and starts counting them again.
+fn get_suppressed_summary(window: &mut Window, timestamp: i64) -> Option<(String, FileLog)> {
+  let (target, log) = window.last_dropped.take()?;
+  let message = format!(
+    "{} messages from {} were suppressed",
+    window.dropped, window.location
+  );
+  window.dropped = 0;
+
+  Some((target, summary(&log, timestamp, message)))
+}
+
+fn get_location(metadata: &tracing::Metadata<'_>) -> String {
+  match (metadata.file(), metadata.line()) {
+    (Some(file), Some(line)) => format!("{}:{}", file, line),
+    _ => metadata.target().into(),
+  }
+}
+
+fn summary(source: &FileLog, timestamp: i64, message: String) -> FileLog {
+  FileLog {
+    timestamp,
+    level: source.level,
+    category: source.category.clone(),
+    message: Some(message),
+    stopwatch: None,
+    error: None,
+    sample_rate: source.sample_rate,
+  }
+}
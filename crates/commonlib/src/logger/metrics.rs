@@ -0,0 +1,171 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use std::{
+  collections::HashMap,
+  fmt::Write,
+  sync::{Arc, Mutex},
+};
+
+use super::{FileLog, LogLevel};
+
+const LEVELS: [LogLevel; 5] = [
+  LogLevel::Error,
+  LogLevel::Warn,
+  LogLevel::Info,
+  LogLevel::Debug,
+  LogLevel::Trace,
+];
+
+/// The amount of logs that went through the logger since it was initialized.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogMetrics {
+  /// The logs emitted, by level. The summaries of the dropped logs aren't counted.
+  pub levels: HashMap<LogLevel, u64>,
+  /// The logs emitted, by category. The logs without a category aren't counted.
+  pub categories: HashMap<String, u64>,
+  /// The logs dropped by the rate limits and the duplicate suppression.
+  pub dropped: u64,
+  /// The logs left out by the sampling.
+  pub sampled_out: u64,
+}
+
+/// Keeps the counters shared by the layer and the logger handle.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+  counters: Arc<Mutex<LogMetrics>>,
+}
+
+impl Metrics {
+  pub fn add_emitted(&self, log: &FileLog) {
+    let mut counters = self.counters.lock().unwrap();
+
+    *counters.levels.entry(log.level).or_insert(0) += 1;
+
+    if let Some(category) = &log.category {
+      *counters.categories.entry(category.clone()).or_insert(0) += 1;
+    }
+  }
+
+  pub fn add_dropped(&self) {
+    self.counters.lock().unwrap().dropped += 1;
+  }
+
+  pub fn add_sampled_out(&self) {
+    self.counters.lock().unwrap().sampled_out += 1;
+  }
+
+  pub fn snapshot(&self) -> LogMetrics {
+    self.counters.lock().unwrap().clone()
+  }
+}
+
+impl LogMetrics {
+  pub fn get_level(&self, level: LogLevel) -> u64 {
+    self.levels.get(&level).copied().unwrap_or(0)
+  }
+
+  pub fn get_category(&self, category: &str) -> u64 {
+    self
+      .categories
+      .get(&category.to_uppercase())
+      .copied()
+      .unwrap_or(0)
+  }
+
+  /// Formats the counters in the Prometheus text exposition format.
+  pub fn to_prometheus(&self) -> String {
+    let mut result = String::new();
+
+    result.push_str("# HELP commonlib_logs_total The logs emitted, by level.\n");
+    result.push_str("# TYPE commonlib_logs_total counter\n");
+    for level in LEVELS {
+      let name = level.to_log_str().unwrap_or_default().trim().to_lowercase();
+      let _ = writeln!(
+        result,
+        "commonlib_logs_total{{level=\"{}\"}} {}",
+        name,
+        self.get_level(level)
+      );
+    }
+
+    let mut categories = self.categories.iter().collect::<Vec<_>>();
+    categories.sort();
+
+    result.push_str("# HELP commonlib_logs_by_category_total The logs emitted, by category.\n");
+    result.push_str("# TYPE commonlib_logs_by_category_total counter\n");
+    for (category, count) in categories {
+      let _ = writeln!(
+        result,
+        "commonlib_logs_by_category_total{{category=\"{}\"}} {}",
+        escape_label(category),
+        count
+      );
+    }
+
+    result.push_str("# HELP commonlib_logs_dropped_total The logs dropped by the rate limits and the duplicate suppression.\n");
+    result.push_str("# TYPE commonlib_logs_dropped_total counter\n");
+    let _ = writeln!(result, "commonlib_logs_dropped_total {}", self.dropped);
+
+    result.push_str("# HELP commonlib_logs_sampled_out_total The logs left out by the sampling.\n");
+    result.push_str("# TYPE commonlib_logs_sampled_out_total counter\n");
+    let _ = writeln!(
+      result,
+      "commonlib_logs_sampled_out_total {}",
+      self.sampled_out
+    );
+
+    result
+  }
+}
+
+fn escape_label(value: &str) -> String {
+  value
+    .replace('\\', "\\\\")
+    .replace('"', "\\\"")
+    .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::logger::test_support;
+
+  fn get_log(level: LogLevel, category: Option<&str>) -> FileLog {
+    FileLog {
+      level,
+      category: category.map(String::from),
+      ..test_support::get_log("counted")
+    }
+  }
+
+  #[test]
+  fn add_emitted_counts_the_levels_and_categories() {
+    let metrics = Metrics::default();
+    metrics.add_emitted(&get_log(LogLevel::Info, Some("DB")));
+    metrics.add_emitted(&get_log(LogLevel::Info, None));
+    metrics.add_emitted(&get_log(LogLevel::Error, Some("DB")));
+
+    let snapshot = metrics.snapshot();
+    assert_eq!(snapshot.get_level(LogLevel::Info), 2);
+    assert_eq!(snapshot.get_level(LogLevel::Error), 1);
+    assert_eq!(snapshot.get_level(LogLevel::Debug), 0);
+    assert_eq!(snapshot.get_category("db"), 2);
+    assert_eq!(snapshot.categories.len(), 1);
+  }
+
+  #[test]
+  fn to_prometheus_has_every_counter() {
+    let metrics = Metrics::default();
+    metrics.add_emitted(&get_log(LogLevel::Warn, Some("A \"B\"")));
+    metrics.add_dropped();
+    metrics.add_sampled_out();
+
+    let text = metrics.snapshot().to_prometheus();
+    assert!(text.contains("commonlib_logs_total{level=\"warn\"} 1\n"));
+    assert!(text.contains("commonlib_logs_total{level=\"trace\"} 0\n"));
+    assert!(text.contains("commonlib_logs_by_category_total{category=\"A \\\"B\\\"\"} 1\n"));
+    assert!(text.contains("commonlib_logs_dropped_total 1\n"));
+    assert!(text.contains("commonlib_logs_sampled_out_total 1\n"));
+  }
+}
@@ -2,27 +2,88 @@
 // Check the README file in the project root for more information.
 
 use std::{
-  collections::HashMap,
+  collections::{hash_map::RandomState, HashMap},
+  hash::{BuildHasher, Hasher},
   marker::PhantomData,
+  ops::Deref,
   path::Path,
-  sync::{Arc, Mutex},
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+  },
+  time::{SystemTime, UNIX_EPOCH},
 };
 
+#[cfg(feature = "logger-audit")]
+use std::collections::HashSet;
+
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-pub use self::{file::FileLog, layer::Layer};
+#[cfg(feature = "logger-config")]
+pub use self::config::{ConfigFormat, LoggerConfig};
+#[cfg(feature = "otel")]
+pub use self::otel::OtelProtocol;
+#[cfg(feature = "logger-redaction")]
+pub use self::redactor::Masking;
+pub use self::{
+  console::ConsoleWrap, file::FileLog, layer::Layer, limiter::RateLimit, metrics::LogMetrics,
+  sampler::Sampling,
+};
 
+#[cfg(feature = "logger-audit")]
+pub mod audit;
+#[cfg(feature = "logger-config")]
+mod config;
+mod console;
 mod fields;
 mod file;
+#[cfg(unix)]
+mod journald;
 mod layer;
+mod limiter;
+mod metrics;
+#[cfg(feature = "logger-network")]
+mod network;
+#[cfg(feature = "otel")]
+mod otel;
+mod panic;
+#[cfg(feature = "logger-redaction")]
+mod redactor;
+mod ring;
+mod sampler;
+#[cfg(unix)]
+mod syslog;
+#[cfg(test)]
+mod test_support;
+pub mod testing;
+mod worker;
 
 #[derive(Debug)]
 pub struct Logger<State = Unlocked> {
   level: LogLevel,
   module_filters: HashMap<String, LogLevel>,
+  #[cfg(feature = "log-bridge")]
+  log_bridge: bool,
+  limiter: limiter::Limiter,
+  sampler: sampler::Sampler,
+  #[cfg(feature = "logger-redaction")]
+  redaction: redactor::RedactionRules,
+  ring_buffer: ring::RingBuffer,
+  panic_hook: panic::PanicHook,
+  metrics: metrics::Metrics,
+  console_layout: console::ConsoleLayout,
+  workers: worker::Workers,
   file_logger: FileLogger,
+  #[cfg(feature = "logger-network")]
+  network_logger: NetworkLogger,
+  #[cfg(unix)]
+  syslog_logger: SyslogLogger,
+  #[cfg(unix)]
+  journald_logger: JournaldLogger,
+  #[cfg(feature = "otel")]
+  otel_exporter: OtelExporter,
   _state: PhantomData<State>,
 }
 
@@ -31,13 +92,62 @@ pub struct FileLogger {
   enable: bool,
   path: String,
   buffer: Arc<Mutex<Vec<FileLog>>>,
+  writer: Arc<Mutex<Option<file::FileWriter>>>,
+  #[cfg(feature = "logger-audit")]
+  audit: bool,
+  #[cfg(feature = "logger-audit")]
+  audit_key: Option<Vec<u8>>,
+  #[cfg(feature = "logger-audit")]
+  audit_categories: HashSet<String>,
+}
+
+#[cfg(feature = "logger-network")]
+#[derive(Debug, Clone)]
+pub struct NetworkLogger {
+  enable: bool,
+  endpoint: String,
+  spool_path: String,
+  buffer: Arc<Mutex<Vec<FileLog>>>,
+}
+
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+pub struct SyslogLogger {
+  enable: bool,
+  path: String,
+  facility: u8,
+}
+
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+pub struct JournaldLogger {
+  enable: bool,
+  path: String,
+}
+
+#[cfg(feature = "otel")]
+#[derive(Debug, Clone)]
+pub struct OtelExporter {
+  enable: bool,
+  endpoint: Option<String>,
+  protocol: OtelProtocol,
+  logs: Arc<Mutex<Vec<otel::OtelLog>>>,
+  spans: Arc<Mutex<Vec<otel::OtelSpan>>>,
+}
+
+/// A logger installed for the current thread, see `Logger::init_scoped`.
+///
+/// Dropping it stops the sinks once they have sent the logs they have buffered.
+pub struct ScopedLogger {
+  logger: Logger<Locked>,
+  _guard: tracing::subscriber::DefaultGuard,
 }
 
 pub struct Unlocked;
 
 pub struct Locked;
 
-#[derive(Debug, PartialEq, PartialOrd, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Clone, Copy, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum LogLevel {
   Off,
@@ -51,9 +161,34 @@ pub enum LogLevel {
 #[derive(Debug, Error)]
 pub enum Error {
   #[error("couldn't initialize the logger")]
-  Init(tracing_subscriber::util::TryInitError),
+  Init(#[source] tracing_subscriber::util::TryInitError),
   #[error("couldn't initialize the logger: it has a level of 'logger::LogLevel::Off'")]
   InitOff,
+  #[cfg(feature = "logger-network")]
+  #[error("couldn't initialize the logger: the network endpoint '{0}' is not valid")]
+  NetworkEndpoint(String),
+  #[error("couldn't initialize the logger: the OpenTelemetry endpoint '{0}' is not valid")]
+  OtelEndpoint(String),
+  #[cfg(feature = "logger-redaction")]
+  #[error("couldn't initialize the logger: the redaction pattern '{0}' is not valid")]
+  RedactionPattern(String, #[source] regex::Error),
+  #[cfg(feature = "log-bridge")]
+  #[error("couldn't install the 'log' bridge")]
+  LogBridge(#[source] log::SetLoggerError),
+  #[error("couldn't start the file logger")]
+  File(#[source] file::Error),
+  #[cfg(feature = "logger-network")]
+  #[error("couldn't start the network logger")]
+  Network(#[source] network::Error),
+  #[cfg(feature = "otel")]
+  #[error("couldn't start the OpenTelemetry exporter")]
+  Otel(#[source] otel::Error),
+  #[error("couldn't connect to the syslog socket")]
+  Syslog(#[source] std::io::Error),
+  #[error("couldn't initialize the logger: the syslog facility '{0}' must be between 0 and 23")]
+  SyslogFacility(u8),
+  #[error("couldn't connect to the journald socket")]
+  Journald(#[source] std::io::Error),
 }
 
 impl Logger {
@@ -61,16 +196,66 @@ impl Logger {
     Self {
       level: LogLevel::Info,
       module_filters: HashMap::new(),
+      #[cfg(feature = "log-bridge")]
+      log_bridge: true,
+      limiter: limiter::Limiter::default(),
+      sampler: sampler::Sampler::default(),
+      #[cfg(feature = "logger-redaction")]
+      redaction: redactor::RedactionRules::default(),
+      ring_buffer: ring::RingBuffer::default(),
+      panic_hook: panic::PanicHook::default(),
+      metrics: metrics::Metrics::default(),
+      console_layout: console::ConsoleLayout::default(),
+      workers: worker::Workers::default(),
       file_logger: FileLogger {
         enable: false,
         path: "./logs".into(),
         buffer: Arc::new(Mutex::new(Vec::new())),
+        writer: Arc::new(Mutex::new(None)),
+        #[cfg(feature = "logger-audit")]
+        audit: false,
+        #[cfg(feature = "logger-audit")]
+        audit_key: None,
+        #[cfg(feature = "logger-audit")]
+        audit_categories: HashSet::new(),
+      },
+      #[cfg(feature = "logger-network")]
+      network_logger: NetworkLogger {
+        enable: false,
+        endpoint: "".into(),
+        spool_path: "./logs/spool".into(),
+        buffer: Arc::new(Mutex::new(Vec::new())),
+      },
+      #[cfg(unix)]
+      syslog_logger: SyslogLogger {
+        enable: false,
+        path: "/dev/log".into(),
+        facility: 1,
+      },
+      #[cfg(unix)]
+      journald_logger: JournaldLogger {
+        enable: false,
+        path: "/run/systemd/journal/socket".into(),
+      },
+      #[cfg(feature = "otel")]
+      otel_exporter: OtelExporter {
+        enable: false,
+        endpoint: None,
+        protocol: OtelProtocol::HttpProtobuf,
+        logs: Arc::new(Mutex::new(Vec::new())),
+        spans: Arc::new(Mutex::new(Vec::new())),
       },
       _state: PhantomData::<Unlocked>,
     }
   }
 }
 
+impl Default for Logger {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
 impl Logger<Unlocked> {
   pub fn set_level(mut self, level: LogLevel) -> Self {
     self.level = level;
@@ -87,57 +272,533 @@ impl Logger<Unlocked> {
     self
   }
 
+  /// Fits the console lines to the terminal, wrapped or truncated under the message column.
+  ///
+  /// The width is detected from the terminal, with the `logger-console-width` feature, or the
+  /// `COLUMNS` environment variable, unless it's given. Without a width the lines are printed
+  /// whole.
+  pub fn setup_console_wrap(mut self, wrap: ConsoleWrap, width: Option<usize>) -> Self {
+    self.console_layout.wrap = wrap;
+    self.console_layout.width = width;
+    self
+  }
+
+  /// Prints the errors in the console as a single line instead of a tree.
+  pub fn set_compact_errors(mut self, enable: bool) -> Self {
+    self.console_layout.compact_errors = enable;
+    self
+  }
+
+  /// Hash chains the records of the file logger so tampering can be detected, it needs the
+  /// `logger-audit` feature.
+  ///
+  /// The audit files are named `YYYYMMDD-audit.csv`, and with a key each one is sealed with an
+  /// HMAC when it's rotated. They are checked with [`audit::verify`].
+  ///
+  /// Without a key anyone can recompute the chain after editing the files, so it only detects
+  /// accidental damage.
+  #[cfg(feature = "logger-audit")]
+  pub fn setup_file_audit(mut self, enable: bool, hmac_key: Option<&[u8]>) -> Self {
+    self.file_logger.audit = enable;
+    self.file_logger.audit_key = hmac_key.map(|k| k.to_vec());
+    self
+  }
+
+  /// Audits only the logs of the category, the rest are written to the plain files.
+  ///
+  /// Without any category every log is audited.
+  #[cfg(feature = "logger-audit")]
+  pub fn add_audit_category(mut self, category: &str) -> Self {
+    self
+      .file_logger
+      .audit_categories
+      .insert(category.to_uppercase());
+    self
+  }
+
+  /// Ships the logs as JSON to `tcp://host:port`, `udp://host:port` or `http://host:port/path`,
+  /// it needs the `logger-network` feature.
+  ///
+  /// The logs that can't be delivered are spooled to disk, by default in `./logs/spool`.
+  #[cfg(feature = "logger-network")]
+  pub fn setup_network_logger(
+    mut self,
+    enable: bool,
+    endpoint: &str,
+    spool_path: Option<String>,
+  ) -> Self {
+    self.network_logger.enable = enable;
+    self.network_logger.endpoint = endpoint.into();
+
+    if let Some(spool_path) = spool_path {
+      self.network_logger.spool_path = spool_path;
+    }
+
+    self
+  }
+
+  /// Sends the logs to the local syslog socket using the RFC 5424 format.
+  ///
+  /// The facility defaults to `1` (user-level messages), initializing the logger fails with one
+  /// above `23`.
+  #[cfg(unix)]
+  pub fn setup_syslog_logger(
+    mut self,
+    enable: bool,
+    path: Option<String>,
+    facility: Option<u8>,
+  ) -> Self {
+    self.syslog_logger.enable = enable;
+
+    if let Some(path) = path {
+      self.syslog_logger.path = path;
+    }
+
+    if let Some(facility) = facility {
+      self.syslog_logger.facility = facility;
+    }
+
+    self
+  }
+
+  /// Sends the logs to the journald socket using its native protocol.
+  #[cfg(unix)]
+  pub fn setup_journald_logger(mut self, enable: bool, path: Option<String>) -> Self {
+    self.journald_logger.enable = enable;
+
+    if let Some(path) = path {
+      self.journald_logger.path = path;
+    }
+
+    self
+  }
+
+  /// Exports the logs and the spans to an OTLP collector.
+  ///
+  /// The protocol defaults to `OtelProtocol::HttpProtobuf` and the endpoint to the local collector
+  /// on the port of the protocol, `http://localhost:4318` or `http://localhost:4317` for gRPC.
+  #[cfg(feature = "otel")]
+  pub fn setup_otel_exporter(
+    mut self,
+    enable: bool,
+    endpoint: Option<String>,
+    protocol: Option<OtelProtocol>,
+  ) -> Self {
+    self.otel_exporter.enable = enable;
+
+    if let Some(endpoint) = endpoint {
+      self.otel_exporter.endpoint = Some(endpoint);
+    }
+
+    if let Some(protocol) = protocol {
+      self.otel_exporter.protocol = protocol;
+    }
+
+    self
+  }
+
+  /// Forwards the records of the `log` crate to the logger, it's enabled by default along with
+  /// the `log-bridge` feature.
+  ///
+  /// Their target is used as the module path, so they go through the same module filters.
+  /// The `log` facade is global, so the bridge is only installed by [`Logger::init`], once per
+  /// process. With [`Logger::build`] or [`Logger::init_scoped`], install
+  /// `tracing_log::LogTracer` to forward the records to the current subscriber.
+  #[cfg(feature = "log-bridge")]
+  pub fn setup_log_bridge(mut self, enable: bool) -> Self {
+    self.log_bridge = enable;
+    self
+  }
+
+  /// Limits how many logs each callsite can emit per interval, `None` removes the limit.
+  ///
+  /// The amount of dropped logs is reported every second, and when the logger is stopped.
+  pub fn set_rate_limit(mut self, limit: Option<RateLimit>) -> Self {
+    self.limiter.rate_limit = limit;
+    self
+  }
+
+  /// Overrides the rate limit for the logs of a category, `None` removes the limit.
+  pub fn add_category_rate_limit(mut self, category: &str, limit: Option<RateLimit>) -> Self {
+    self
+      .limiter
+      .category_rate_limits
+      .insert(category.to_uppercase(), limit);
+    self
+  }
+
+  /// Collapses identical consecutive logs of a callsite into a "last message repeated X times"
+  /// log, which is reported every second, and when the logger is stopped.
+  pub fn set_dedup(mut self, enable: bool) -> Self {
+    self.limiter.dedup = enable;
+    self
+  }
+
+  /// Overrides whether the identical consecutive logs of a category are collapsed.
+  pub fn add_category_dedup(mut self, category: &str, enable: bool) -> Self {
+    self
+      .limiter
+      .category_dedups
+      .insert(category.to_uppercase(), enable);
+    self
+  }
+
+  /// Keeps only part of the logs of a level, `None` keeps all of them.
+  ///
+  /// The errors and the warnings are never sampled out.
+  pub fn set_sampling(mut self, level: LogLevel, sampling: Option<Sampling>) -> Self {
+    match sampling {
+      Some(sampling) => self.sampler.level_samplings.insert(level, sampling),
+      None => self.sampler.level_samplings.remove(&level),
+    };
+    self
+  }
+
+  /// Overrides the sampling of a level for the logs of a module, `None` keeps all of them.
+  pub fn add_module_sampling(
+    mut self,
+    module_name: &str,
+    level: LogLevel,
+    sampling: Option<Sampling>,
+  ) -> Self {
+    self
+      .sampler
+      .module_samplings
+      .insert((module_name.into(), level), sampling);
+    self
+  }
+
+  /// Masks the values of a field, like `password` or `token`, in the text of the logs, it needs
+  /// the `logger-redaction` feature.
+  ///
+  /// The field is matched without case in text like `password=1234` or `"token": "abcd"`, in
+  /// the messages, the errors, the categories and the stopwatches.
+  #[cfg(feature = "logger-redaction")]
+  pub fn add_redacted_field(mut self, name: &str, masking: Masking) -> Self {
+    self.redaction.fields.push((name.into(), masking));
+    self
+  }
+
+  /// Masks every match of the regex in the text of the logs.
+  ///
+  /// The pattern is validated when the logger is initialized.
+  #[cfg(feature = "logger-redaction")]
+  pub fn add_redaction_pattern(mut self, pattern: &str, masking: Masking) -> Self {
+    self.redaction.patterns.push((pattern.into(), masking));
+    self
+  }
+
+  /// Logs the panics as errors with the `PANIC` category, optionally with a backtrace.
+  ///
+  /// The hook is installed by [`Logger::init`], it flushes the file logger and then runs the
+  /// previous hook.
+  ///
+  /// The panics skip the sampling and the rate limits, so they are always logged.
+  pub fn setup_panic_hook(mut self, enable: bool, backtrace: bool) -> Self {
+    self.panic_hook.enable = enable;
+    self.panic_hook.backtrace = backtrace;
+    self
+  }
+
+  /// Keeps the last `capacity` logs in memory, down to the level of the ring buffer, which is
+  /// debug by default, even when it's below the level of the logger.
+  ///
+  /// They are dumped when an error is logged or, if the logger is installed with
+  /// [`Logger::init`], when the process panics. The dumps are written as CSV files into the
+  /// `dump_path` directory, or to stderr without one.
+  pub fn setup_ring_buffer(
+    mut self,
+    enable: bool,
+    capacity: usize,
+    dump_path: Option<String>,
+  ) -> Self {
+    self.ring_buffer.enable = enable;
+    self.ring_buffer.capacity = capacity.max(1);
+    self.ring_buffer.dump_path = dump_path;
+    self
+  }
+
+  /// The lowest level of the logs kept by the ring buffer, see [`Logger::setup_ring_buffer`].
+  pub fn set_ring_buffer_level(mut self, level: LogLevel) -> Self {
+    self.ring_buffer.level = level;
+    self
+  }
+
   pub fn add_module_filter(mut self, module_name: &str, level: LogLevel) -> Self {
     self.module_filters.insert(module_name.into(), level);
     self
   }
 
+  /// Builds the layer without installing it, so it can be composed with other layers.
+  ///
+  /// The sinks are started right away and run until [`Logger::stop`] is called.
+  pub fn build(self) -> Result<(Logger<Locked>, Layer), Error> {
+    let layer = self.build_layer()?;
+
+    Ok((self.start()?, layer))
+  }
+
+  /// Installs the logger as the global default subscriber, along with the `log` bridge when the
+  /// `log-bridge` feature is enabled.
+  ///
+  /// It fails if there is already a global subscriber or a `log` logger, in which case nothing
+  /// is installed.
   pub fn init(self) -> Result<Logger<Locked>, Error> {
+    let layer = self.build_layer()?;
+    let panic_layer = layer.clone();
+    let logger = self.start()?;
+
+    // With a global subscriber already set 'try_init' fails, so the bridge isn't installed
+    #[cfg(feature = "log-bridge")]
+    let result = match tracing::dispatcher::has_been_set() {
+      true => Ok(()),
+      false => logger.init_log_bridge(),
+    };
+    #[cfg(not(feature = "log-bridge"))]
+    let result = Ok(());
+
+    let result = result.and_then(|_| {
+      tracing_subscriber::registry()
+        .with(layer)
+        .try_init()
+        .map_err(Error::Init)
+    });
+
+    if let Err(error) = result {
+      logger.stop();
+      return Err(error);
+    }
+
+    if logger.panic_hook.enable || logger.ring_buffer.enable {
+      panic::install(
+        logger.panic_hook.clone(),
+        panic_layer,
+        logger.file_logger.enable.then(|| logger.get_file_logger()),
+      );
+    }
+
+    Ok(logger)
+  }
+
+  /// Installs the logger as the default subscriber of the current thread until the returned
+  /// guard is dropped.
+  pub fn init_scoped(self) -> Result<ScopedLogger, Error> {
+    let layer = self.build_layer()?;
+
+    let guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(layer));
+
+    Ok(ScopedLogger {
+      logger: self.start()?,
+      _guard: guard,
+    })
+  }
+
+  fn build_layer(&self) -> Result<Layer, Error> {
     if self.level == LogLevel::Off {
       return Err(Error::InitOff);
     }
 
-    tracing_subscriber::registry()
-      .with(Layer {
-        level: self.level,
-        module_filters: self.module_filters.clone(),
-        file_logger: self.file_logger.clone(),
+    #[cfg(feature = "logger-network")]
+    if self.network_logger.enable
+      && network::Endpoint::parse(&self.network_logger.endpoint).is_none()
+    {
+      return Err(Error::NetworkEndpoint(self.network_logger.endpoint.clone()));
+    }
+
+    #[cfg(feature = "otel")]
+    if self.otel_exporter.enable
+      && !otel::OtelExporter::is_valid_endpoint(
+        self.otel_exporter.get_endpoint(),
+        self.otel_exporter.protocol,
+      )
+    {
+      return Err(Error::OtelEndpoint(
+        self.otel_exporter.get_endpoint().into(),
+      ));
+    }
+
+    #[cfg(unix)]
+    let syslog_logger = self
+      .syslog_logger
+      .enable
+      .then(|| {
+        syslog::SyslogSink::connect(
+          Path::new(&self.syslog_logger.path),
+          self.syslog_logger.facility,
+        )
+        .map(Arc::new)
       })
-      .try_init()
-      .map_err(Error::Init)?;
+      .transpose()?;
+
+    #[cfg(unix)]
+    let journald_logger = self
+      .journald_logger
+      .enable
+      .then(|| journald::JournaldSink::connect(Path::new(&self.journald_logger.path)).map(Arc::new))
+      .transpose()?;
+
+    let layer = Layer {
+      level: self.level,
+      module_filters: self.module_filters.clone(),
+      file_logger: self.file_logger.clone(),
+      #[cfg(feature = "logger-network")]
+      network_logger: self.network_logger.clone(),
+      #[cfg(unix)]
+      syslog_logger,
+      #[cfg(unix)]
+      journald_logger,
+      #[cfg(feature = "otel")]
+      otel_exporter: self.otel_exporter.clone(),
+      capture: None,
+      limiter: self.limiter.clone(),
+      sampler: self.sampler.clone(),
+      #[cfg(feature = "logger-redaction")]
+      redactor: redactor::Redactor::new(&self.redaction)?,
+      ring_buffer: self.ring_buffer.clone(),
+      metrics: self.metrics.clone(),
+      console_layout: self.console_layout.clone(),
+    };
+
+    // Spawned before the sinks, so the summaries of its last round reach their last round
+    if self.limiter.is_enabled() {
+      self.workers.spawn(layer::SummaryWorker(layer.clone()));
+    }
 
+    Ok(layer)
+  }
+
+  fn start_sinks(&self) -> Result<(), Error> {
     if self.file_logger.enable {
-      file::FileLogger::new(
-        Path::new(&self.file_logger.path),
-        self.file_logger.clone().buffer,
+      self
+        .get_file_logger()
+        .init(&self.workers)
+        .map_err(Error::File)?;
+    }
+
+    #[cfg(feature = "logger-network")]
+    if self.network_logger.enable {
+      if let Some(endpoint) = network::Endpoint::parse(&self.network_logger.endpoint) {
+        network::NetworkLogger::new(
+          endpoint,
+          Path::new(&self.network_logger.spool_path),
+          self.network_logger.clone().buffer,
+        )
+        .init(&self.workers)
+        .map_err(Error::Network)?;
+      }
+    }
+
+    #[cfg(feature = "otel")]
+    if self.otel_exporter.enable {
+      otel::OtelExporter::new(
+        self.otel_exporter.get_endpoint(),
+        self.otel_exporter.protocol,
+        self.otel_exporter.logs.clone(),
+        self.otel_exporter.spans.clone(),
       )
-      .init();
+      .init(&self.workers)
+      .map_err(Error::Otel)?;
+    }
+
+    Ok(())
+  }
+
+  fn start(self) -> Result<Logger<Locked>, Error> {
+    // The sinks already started are stopped if another one can't start
+    if let Err(error) = self.start_sinks() {
+      self.workers.stop();
+      return Err(error);
     }
 
     Ok(Logger {
       level: self.level,
       module_filters: self.module_filters,
+      #[cfg(feature = "log-bridge")]
+      log_bridge: self.log_bridge,
+      limiter: self.limiter,
+      sampler: self.sampler,
+      #[cfg(feature = "logger-redaction")]
+      redaction: self.redaction,
+      ring_buffer: self.ring_buffer,
+      panic_hook: self.panic_hook,
+      metrics: self.metrics,
+      console_layout: self.console_layout,
+      workers: self.workers,
       file_logger: self.file_logger,
+      #[cfg(feature = "logger-network")]
+      network_logger: self.network_logger,
+      #[cfg(unix)]
+      syslog_logger: self.syslog_logger,
+      #[cfg(unix)]
+      journald_logger: self.journald_logger,
+      #[cfg(feature = "otel")]
+      otel_exporter: self.otel_exporter,
       _state: PhantomData::<Locked>,
     })
   }
 }
 
+impl<State> Logger<State> {
+  #[cfg(feature = "log-bridge")]
+  fn init_log_bridge(&self) -> Result<(), Error> {
+    if self.log_bridge {
+      tracing_log::LogTracer::init().map_err(Error::LogBridge)?;
+    }
+
+    Ok(())
+  }
+
+  fn get_file_logger(&self) -> file::FileLogger {
+    file::FileLogger::new(&self.file_logger)
+  }
+}
+
 impl Logger<Locked> {
-  pub fn flush() {
-    // TODO: Make the 'flush' function
-    todo!()
+  /// The amount of logs emitted, dropped and sampled out since the logger was initialized.
+  pub fn metrics(&self) -> LogMetrics {
+    self.metrics.snapshot()
+  }
+
+  /// Sends the logs the sinks have buffered right away, without waiting for their next round.
+  pub fn flush(&self) {
+    self.workers.flush();
   }
 
-  pub fn stop() {
-    // TODO: Make the 'stop' function
-    todo!()
+  /// Stops the sinks running in the background once they have sent the logs they have buffered.
+  ///
+  /// The logs emitted afterwards are only written to the console and the socket sinks.
+  pub fn stop(&self) {
+    self.workers.stop();
+  }
+}
+
+impl Drop for ScopedLogger {
+  fn drop(&mut self) {
+    self.logger.stop();
+  }
+}
+
+impl Deref for ScopedLogger {
+  type Target = Logger<Locked>;
+
+  fn deref(&self) -> &Self::Target {
+    &self.logger
+  }
+}
+
+#[cfg(feature = "otel")]
+impl OtelExporter {
+  fn get_endpoint(&self) -> &str {
+    self
+      .endpoint
+      .as_deref()
+      .unwrap_or(self.protocol.default_endpoint())
   }
 }
 
 impl LogLevel {
   fn from_str(string: &str) -> Option<Self> {
-    match string {
+    match string.to_lowercase().as_str() {
       "error" => Some(Self::Error),
       "warn" => Some(Self::Warn),
       "info" => Some(Self::Info),
@@ -156,18 +817,86 @@ impl LogLevel {
       Self::Trace => Some("TRACE".into()),
       _ => None,
     }
-    .into()
+  }
+
+  /// Maps the level to its RFC 5424 severity.
+  pub fn to_syslog_severity(self) -> Option<u8> {
+    match self {
+      Self::Error => Some(3),
+      Self::Warn => Some(4),
+      Self::Info => Some(6),
+      Self::Debug => Some(7),
+      Self::Trace => Some(7),
+      _ => None,
+    }
+  }
+}
+
+// The levels are matched without case, so `DEBUG` can be set from the environment
+impl<'de> Deserialize<'de> for LogLevel {
+  fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let string = String::deserialize(deserializer)?;
+
+    match string.to_lowercase().as_str() {
+      "off" => Ok(Self::Off),
+      _ => Self::from_str(&string).ok_or_else(|| {
+        serde::de::Error::unknown_variant(
+          &string,
+          &["off", "error", "warn", "info", "debug", "trace"],
+        )
+      }),
+    }
+  }
+}
+
+#[cfg(feature = "log-bridge")]
+impl From<log::Level> for LogLevel {
+  fn from(value: log::Level) -> Self {
+    match value {
+      log::Level::Error => Self::Error,
+      log::Level::Warn => Self::Warn,
+      log::Level::Info => Self::Info,
+      log::Level::Debug => Self::Debug,
+      log::Level::Trace => Self::Trace,
+    }
   }
 }
 
 impl From<&tracing::Level> for LogLevel {
   fn from(value: &tracing::Level) -> Self {
-    match value {
-      &tracing::Level::ERROR => Self::Error,
-      &tracing::Level::WARN => Self::Warn,
-      &tracing::Level::INFO => Self::Info,
-      &tracing::Level::DEBUG => Self::Debug,
-      &tracing::Level::TRACE => Self::Trace,
+    match *value {
+      tracing::Level::ERROR => Self::Error,
+      tracing::Level::WARN => Self::Warn,
+      tracing::Level::INFO => Self::Info,
+      tracing::Level::DEBUG => Self::Debug,
+      tracing::Level::TRACE => Self::Trace,
     }
   }
 }
+
+fn random_u64() -> u64 {
+  static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+  let nanos = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_nanos() as u64)
+    .unwrap_or(0);
+
+  let mut hasher = RandomState::new().build_hasher();
+  hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+  hasher.write_u64(nanos);
+
+  hasher.finish()
+}
+
+/// Reports the failures of the sinks running in the background, which can't log them.
+fn report_error<E: std::error::Error + Send + Sync + 'static>(error: E) {
+  eprint!("{}", crate::Error::from_error(error).set_category("LOGGER"));
+}
+
+fn get_app_name() -> String {
+  std::env::current_exe()
+    .ok()
+    .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+    .unwrap_or("-".into())
+}
@@ -0,0 +1,549 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use std::{
+  fs,
+  io::{Read, Write},
+  net::{TcpStream, ToSocketAddrs, UdpSocket},
+  path::{Path, PathBuf},
+  sync::{Arc, Mutex},
+  time::{Duration, Instant},
+};
+
+use thiserror::Error;
+
+use super::{
+  file::{self, FileWriter},
+  worker::{Worker, Workers},
+  FileLog,
+};
+
+const BATCH_SIZE: usize = 100;
+const TIMEOUT: Duration = Duration::from_secs(5);
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+// The largest UDP payload over IPv4
+const MAX_DATAGRAM_SIZE: usize = 65_507;
+
+/// Ships the logs as JSON to a remote collector.
+///
+/// While the endpoint can't be reached the logs are spooled to disk and they are sent again,
+/// oldest first, once the connection is recovered. Delivery is at least once: a spool file that
+/// fails halfway through is sent again from the start. A batch that would fail the same way
+/// every time, because it can't be encoded or the endpoint rejects it, is reported and dropped.
+#[derive(Debug)]
+pub struct NetworkLogger {
+  endpoint: Endpoint,
+  spool_path: PathBuf,
+  buffer: Arc<Mutex<Vec<FileLog>>>,
+  spool: Option<FileWriter>,
+  connection: Option<TcpStream>,
+  backoff: Duration,
+  retry_at: Option<Instant>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Endpoint {
+  /// Newline-delimited JSON over a persistent connection.
+  Tcp(String),
+  /// One JSON record per datagram.
+  Udp(String),
+  /// A JSON array per batch sent as a `POST` request.
+  Http { address: String, path: String },
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+  #[error("couldn't connect to the endpoint")]
+  Connect(std::io::Error),
+  #[error("couldn't send the logs")]
+  Send(std::io::Error),
+  #[error("the endpoint answered with '{0}'")]
+  Http(String),
+  #[error("the endpoint rejected the logs with '{0}'")]
+  Rejected(String),
+  #[error("couldn't encode the log")]
+  Encode(serde_json::Error),
+  #[error("the log of {0} bytes doesn't fit in a datagram")]
+  Oversized(usize),
+  #[error("couldn't create the spool directory")]
+  CreateSpool(std::io::Error),
+  #[error("couldn't write the spool")]
+  Spool(file::Error),
+  #[error("couldn't read the spool")]
+  ReadSpool(std::io::Error),
+  #[error("couldn't deserialize the spooled log")]
+  Deserialize(csv::Error),
+}
+
+impl NetworkLogger {
+  pub fn new(endpoint: Endpoint, spool_path: &Path, buffer: Arc<Mutex<Vec<FileLog>>>) -> Self {
+    Self {
+      endpoint,
+      spool_path: file::get_dir_path(spool_path),
+      buffer,
+      spool: None,
+      connection: None,
+      backoff: INITIAL_BACKOFF,
+      retry_at: None,
+    }
+  }
+
+  /// Ships the logs every second, the spool directory is created right away.
+  pub fn init(self, workers: &Workers) -> Result<(), Error> {
+    fs::create_dir_all(&self.spool_path).map_err(Error::CreateSpool)?;
+    workers.spawn(Mutex::new(self));
+
+    Ok(())
+  }
+
+  /// Sends the buffered logs, or spools them while the endpoint can't be reached.
+  fn ship(&mut self) -> Result<(), Error> {
+    let logs = {
+      let mut buffer = self.buffer.lock().unwrap();
+
+      std::mem::take(&mut *buffer)
+    };
+
+    if self.retry_at.is_some_and(|r| r > Instant::now()) {
+      return self.spool(logs);
+    }
+
+    match self.deliver(&logs) {
+      Ok(_) => {
+        self.backoff = INITIAL_BACKOFF;
+        self.retry_at = None;
+
+        Ok(())
+      }
+      Err(error) => {
+        self.connection = None;
+        self.retry_at = Some(Instant::now() + self.backoff);
+        self.backoff = (self.backoff * 2).min(MAX_BACKOFF);
+
+        super::report_error(error);
+        self.spool(logs)
+      }
+    }
+  }
+
+  fn deliver(&mut self, logs: &[FileLog]) -> Result<(), Error> {
+    // Close the spool so the files are complete before sending them
+    if let Some(mut writer) = self.spool.take() {
+      writer.flush().map_err(Error::Spool)?;
+    }
+
+    for path in self.get_spool_files()? {
+      let logs = read_spool(&path)?;
+
+      self.send(&logs)?;
+      fs::remove_file(&path).map_err(Error::ReadSpool)?;
+    }
+
+    self.send(logs)
+  }
+
+  fn spool(&mut self, logs: Vec<FileLog>) -> Result<(), Error> {
+    if logs.is_empty() {
+      return Ok(());
+    }
+
+    let writer = match &mut self.spool {
+      Some(writer) => writer,
+      None => self
+        .spool
+        .insert(FileWriter::new(&self.spool_path).map_err(Error::Spool)?),
+    };
+
+    for log in logs {
+      writer.write(log).map_err(Error::Spool)?;
+    }
+
+    writer.flush().map_err(Error::Spool)
+  }
+
+  fn get_spool_files(&self) -> Result<Vec<PathBuf>, Error> {
+    if !self.spool_path.exists() {
+      return Ok(Vec::new());
+    }
+
+    let mut paths = fs::read_dir(&self.spool_path)
+      .map_err(Error::ReadSpool)?
+      .filter_map(|e| e.ok().map(|e| e.path()))
+      .filter(|p| p.is_file() && p.to_string_lossy().ends_with("-log.csv"))
+      .collect::<Vec<_>>();
+    paths.sort();
+
+    Ok(paths)
+  }
+
+  fn send(&mut self, logs: &[FileLog]) -> Result<(), Error> {
+    for batch in logs.chunks(BATCH_SIZE) {
+      match self.send_batch(batch) {
+        Err(error) if error.is_permanent() => super::report_error(error),
+        result => result?,
+      }
+    }
+
+    Ok(())
+  }
+
+  fn send_batch(&mut self, batch: &[FileLog]) -> Result<(), Error> {
+    match &self.endpoint {
+      Endpoint::Tcp(address) => {
+        let mut body = Vec::new();
+        for log in batch {
+          serde_json::to_writer(&mut body, log).map_err(Error::Encode)?;
+          body.push(b'\n');
+        }
+
+        let connection = match &mut self.connection {
+          Some(connection) => connection,
+          None => self.connection.insert(connect(address)?),
+        };
+
+        connection.write_all(&body).map_err(Error::Send)?;
+        connection.flush().map_err(Error::Send)?;
+      }
+      Endpoint::Udp(address) => {
+        // Checked before sending any of them, so a dropped batch isn't half delivered
+        let bodies = batch
+          .iter()
+          .map(|log| {
+            let body = serde_json::to_vec(log).map_err(Error::Encode)?;
+
+            match body.len() > MAX_DATAGRAM_SIZE {
+              true => Err(Error::Oversized(body.len())),
+              false => Ok(body),
+            }
+          })
+          .collect::<Result<Vec<_>, _>>()?;
+
+        let socket = UdpSocket::bind("0.0.0.0:0").map_err(Error::Connect)?;
+        socket.connect(address).map_err(Error::Connect)?;
+
+        for body in bodies {
+          socket.send(&body).map_err(Error::Send)?;
+        }
+      }
+      Endpoint::Http { address, path } => {
+        let body = serde_json::to_vec(batch).map_err(Error::Encode)?;
+        post(address, path, "application/json", &body)?;
+      }
+    }
+
+    Ok(())
+  }
+}
+
+impl Error {
+  /// Whether sending the same logs again would fail the same way.
+  fn is_permanent(&self) -> bool {
+    matches!(
+      self,
+      Self::Rejected(_) | Self::Encode(_) | Self::Oversized(_)
+    )
+  }
+}
+
+impl Worker for Mutex<NetworkLogger> {
+  fn work(&self) {
+    let mut network_logger = self.lock().unwrap();
+
+    // The logs of a failed round are lost, but the next ones are still shipped
+    if let Err(error) = network_logger.ship() {
+      network_logger.spool = None;
+      super::report_error(error);
+    }
+  }
+}
+
+impl Endpoint {
+  /// Parses endpoints like `tcp://host:port`, `udp://host:port` or `http://host:port/path`.
+  pub fn parse(string: &str) -> Option<Self> {
+    let (scheme, rest) = string.split_once("://")?;
+    if rest.is_empty() {
+      return None;
+    }
+
+    match scheme {
+      "tcp" => Some(Self::Tcp(rest.into())),
+      "udp" => Some(Self::Udp(rest.into())),
+      "http" => {
+        let (address, path) = match rest.find('/') {
+          Some(idx) => (&rest[..idx], &rest[idx..]),
+          None => (rest, "/"),
+        };
+
+        let address = match address.contains(':') {
+          true => address.to_string(),
+          false => format!("{}:80", address),
+        };
+
+        Some(Self::Http {
+          address,
+          path: path.into(),
+        })
+      }
+      _ => None,
+    }
+  }
+}
+
+fn connect(address: &str) -> Result<TcpStream, Error> {
+  let mut last_error = None;
+
+  for socket_address in address.to_socket_addrs().map_err(Error::Connect)? {
+    match TcpStream::connect_timeout(&socket_address, TIMEOUT) {
+      Ok(stream) => {
+        stream
+          .set_write_timeout(Some(TIMEOUT))
+          .map_err(Error::Connect)?;
+        stream
+          .set_read_timeout(Some(TIMEOUT))
+          .map_err(Error::Connect)?;
+
+        return Ok(stream);
+      }
+      Err(error) => last_error = Some(error),
+    }
+  }
+
+  Err(Error::Connect(last_error.unwrap_or(std::io::Error::new(
+    std::io::ErrorKind::NotFound,
+    "the address didn't resolve",
+  ))))
+}
+
+pub fn post(address: &str, path: &str, content_type: &str, body: &[u8]) -> Result<(), Error> {
+  let mut stream = connect(address)?;
+
+  let head = format!(
+    "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+    path,
+    address,
+    content_type,
+    body.len()
+  );
+
+  stream.write_all(head.as_bytes()).map_err(Error::Send)?;
+  stream.write_all(body).map_err(Error::Send)?;
+  stream.flush().map_err(Error::Send)?;
+
+  let mut response = Vec::new();
+  stream.read_to_end(&mut response).map_err(Error::Send)?;
+
+  let response = String::from_utf8_lossy(&response);
+  let status_line = response.lines().next().unwrap_or("");
+  let status = status_line.split(' ').nth(1).unwrap_or("");
+
+  match status.chars().next() {
+    Some('2') => Ok(()),
+    Some('4') => Err(Error::Rejected(status_line.into())),
+    _ => Err(Error::Http(status_line.into())),
+  }
+}
+
+fn read_spool(path: &Path) -> Result<Vec<FileLog>, Error> {
+  // The spool files written before a column was added have shorter records
+  let mut reader = csv::ReaderBuilder::new()
+    .flexible(true)
+    .from_path(path)
+    .map_err(Error::Deserialize)?;
+
+  reader
+    .deserialize()
+    .collect::<Result<Vec<FileLog>, _>>()
+    .map_err(Error::Deserialize)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{
+    io::{BufRead, BufReader},
+    net::TcpListener,
+    thread,
+  };
+
+  use super::*;
+  use crate::logger::test_support::{get_log, TempDir};
+
+  fn get_logger(spool_path: &Path, endpoint: Endpoint) -> NetworkLogger {
+    NetworkLogger::new(endpoint, spool_path, Arc::new(Mutex::new(Vec::new())))
+  }
+
+  fn push(logger: &NetworkLogger, messages: &[&str]) {
+    let mut buffer = logger.buffer.lock().unwrap();
+    buffer.extend(messages.iter().map(|m| get_log(m)));
+  }
+
+  fn read_messages(reader: &mut impl BufRead, count: usize) -> Vec<String> {
+    (0..count)
+      .map(|_| {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+
+        let log: FileLog = serde_json::from_str(&line).unwrap();
+        log.message.unwrap()
+      })
+      .collect()
+  }
+
+  #[test]
+  fn delivers_the_logs_as_json_lines() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let spool_path = TempDir::new("spool-delivery");
+    let mut logger = get_logger(&spool_path, Endpoint::Tcp(address));
+
+    push(&logger, &["first", "second"]);
+    logger.ship().unwrap();
+
+    let (stream, _) = listener.accept().unwrap();
+    let mut reader = BufReader::new(stream);
+    assert_eq!(read_messages(&mut reader, 2), ["first", "second"]);
+    assert!(logger.get_spool_files().unwrap().is_empty());
+  }
+
+  #[test]
+  fn spools_and_resends_while_the_endpoint_is_down() {
+    // The port is free once the listener is dropped
+    let address = {
+      let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+      listener.local_addr().unwrap().to_string()
+    };
+    let spool_path = TempDir::new("spool-resend");
+    let mut logger = get_logger(&spool_path, Endpoint::Tcp(address.clone()));
+
+    push(&logger, &["lost"]);
+    logger.ship().unwrap();
+
+    let spool_files = logger.get_spool_files().unwrap();
+    assert_eq!(spool_files.len(), 1);
+    assert!(logger.retry_at.is_some());
+
+    // Spooled without trying again while backing off
+    push(&logger, &["backing off"]);
+    logger.ship().unwrap();
+    assert_eq!(read_spool(&spool_files[0]).unwrap().len(), 2);
+
+    let listener = TcpListener::bind(&address).unwrap();
+    logger.retry_at = None;
+    push(&logger, &["recovered"]);
+    logger.ship().unwrap();
+
+    let (stream, _) = listener.accept().unwrap();
+    let mut reader = BufReader::new(stream);
+    assert_eq!(
+      read_messages(&mut reader, 3),
+      ["lost", "backing off", "recovered"]
+    );
+    assert!(logger.get_spool_files().unwrap().is_empty());
+    assert_eq!(logger.backoff, INITIAL_BACKOFF);
+  }
+
+  #[test]
+  fn posts_the_batches_over_http() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let server = thread::spawn(move || {
+      let (mut stream, _) = listener.accept().unwrap();
+      let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+      let mut head = Vec::new();
+      let mut content_length = 0;
+      loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if let Some(length) = line.strip_prefix("Content-Length: ") {
+          content_length = length.trim().parse().unwrap();
+        }
+        if line == "\r\n" {
+          break;
+        }
+        head.push(line);
+      }
+
+      let mut body = vec![0; content_length];
+      reader.read_exact(&mut body).unwrap();
+      stream.write_all(b"HTTP/1.1 200 OK\r\n\r\n").unwrap();
+
+      (head, serde_json::from_slice::<Vec<FileLog>>(&body).unwrap())
+    });
+
+    let spool_path = TempDir::new("spool-http");
+    let mut logger = get_logger(
+      &spool_path,
+      Endpoint::parse(&format!("http://{}/logs", address)).unwrap(),
+    );
+    push(&logger, &["posted"]);
+    logger.ship().unwrap();
+
+    let (head, logs) = server.join().unwrap();
+    assert_eq!(head[0], "POST /logs HTTP/1.1\r\n");
+    assert_eq!(logs[0].message.as_deref(), Some("posted"));
+  }
+
+  #[test]
+  fn drops_the_batches_the_endpoint_rejects() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let server = thread::spawn(move || {
+      let (mut stream, _) = listener.accept().unwrap();
+      stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+      let _ = stream.read(&mut [0; 4096]);
+      stream
+        .write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n")
+        .unwrap();
+    });
+
+    let spool_path = TempDir::new("spool-rejected");
+    let mut logger = get_logger(
+      &spool_path,
+      Endpoint::parse(&format!("http://{}/logs", address)).unwrap(),
+    );
+    push(&logger, &["malformed"]);
+    logger.ship().unwrap();
+    server.join().unwrap();
+
+    assert!(logger.get_spool_files().unwrap().is_empty());
+    assert!(logger.retry_at.is_none());
+  }
+
+  #[test]
+  fn drops_the_batches_that_dont_fit_in_a_datagram() {
+    let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let address = server.local_addr().unwrap().to_string();
+    let spool_path = TempDir::new("spool-oversized");
+    let mut logger = get_logger(&spool_path, Endpoint::Udp(address));
+
+    push(&logger, &[&"x".repeat(MAX_DATAGRAM_SIZE)]);
+    logger.ship().unwrap();
+
+    assert!(logger.get_spool_files().unwrap().is_empty());
+    assert!(logger.retry_at.is_none());
+
+    push(&logger, &["fits"]);
+    logger.ship().unwrap();
+
+    let mut buffer = [0; 1024];
+    let len = server.recv(&mut buffer).unwrap();
+    let log: FileLog = serde_json::from_slice(&buffer[..len]).unwrap();
+    assert_eq!(log.message.as_deref(), Some("fits"));
+  }
+
+  #[test]
+  fn parses_the_endpoints() {
+    assert!(
+      matches!(Endpoint::parse("tcp://localhost:514"), Some(Endpoint::Tcp(a)) if a == "localhost:514")
+    );
+    assert!(matches!(
+      Endpoint::parse("http://collector"),
+      Some(Endpoint::Http { address, path }) if address == "collector:80" && path == "/"
+    ));
+    assert!(Endpoint::parse("ftp://collector").is_none());
+    assert!(Endpoint::parse("tcp://").is_none());
+  }
+}
@@ -0,0 +1,672 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use std::{
+  sync::{Arc, Mutex},
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use opentelemetry_proto::tonic::{
+  collector::{
+    logs::v1::{logs_service_client::LogsServiceClient, ExportLogsServiceRequest},
+    trace::v1::{trace_service_client::TraceServiceClient, ExportTraceServiceRequest},
+  },
+  common::v1::{any_value::Value, AnyValue, InstrumentationScope, KeyValue},
+  logs::v1::{LogRecord, ResourceLogs, ScopeLogs},
+  resource::v1::Resource,
+  trace::v1::{span::SpanKind, ResourceSpans, ScopeSpans, Span},
+};
+use prost::Message;
+use serde::Deserialize;
+use thiserror::Error;
+use tonic::transport::Channel;
+
+use super::{
+  get_app_name, network, random_u64,
+  worker::{Worker, Workers},
+  FileLog, LogLevel,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OtelProtocol {
+  /// OTLP/HTTP with protobuf payloads, usually on port `4318`.
+  HttpProtobuf,
+  /// OTLP/gRPC, usually on port `4317`.
+  Grpc,
+}
+
+/// A log record along with the span it was emitted in.
+#[derive(Debug, Clone)]
+pub struct OtelLog {
+  pub log: FileLog,
+  pub target: String,
+  pub trace_id: Option<[u8; 16]>,
+  pub span_id: Option<[u8; 8]>,
+  /// The fields of the event that aren't part of the log.
+  pub attributes: Vec<(String, String)>,
+}
+
+/// A span, stored in the span extensions while it's open.
+#[derive(Debug, Clone)]
+pub struct OtelSpan {
+  pub trace_id: [u8; 16],
+  pub span_id: [u8; 8],
+  pub parent_span_id: Option<[u8; 8]>,
+  pub name: String,
+  pub target: String,
+  pub start_time: u64,
+  pub end_time: u64,
+  pub attributes: Vec<(String, String)>,
+}
+
+#[derive(Debug)]
+pub struct OtelExporter {
+  endpoint: String,
+  protocol: OtelProtocol,
+  logs: Arc<Mutex<Vec<OtelLog>>>,
+  spans: Arc<Mutex<Vec<OtelSpan>>>,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+  #[error("couldn't start the gRPC runtime")]
+  Runtime(std::io::Error),
+  #[error("couldn't export through OTLP/HTTP")]
+  Http(network::Error),
+  #[error("couldn't create the OTLP/gRPC channel")]
+  GrpcConnect(tonic::transport::Error),
+  #[error("couldn't export through OTLP/gRPC")]
+  Grpc(Box<tonic::Status>),
+}
+
+#[derive(Debug, Clone)]
+struct GrpcClients {
+  logs: LogsServiceClient<Channel>,
+  spans: TraceServiceClient<Channel>,
+}
+
+#[derive(Debug)]
+struct OtelWorker {
+  exporter: OtelExporter,
+  runtime: tokio::runtime::Runtime,
+  clients: Mutex<Option<GrpcClients>>,
+}
+
+/// Collects every field of a span or an event as a string attribute.
+pub struct SpanAttributes<'a>(pub &'a mut Vec<(String, String)>);
+
+impl OtelProtocol {
+  pub(super) fn default_endpoint(self) -> &'static str {
+    match self {
+      Self::HttpProtobuf => "http://localhost:4318",
+      Self::Grpc => "http://localhost:4317",
+    }
+  }
+}
+
+impl OtelExporter {
+  pub fn new(
+    endpoint: &str,
+    protocol: OtelProtocol,
+    logs: Arc<Mutex<Vec<OtelLog>>>,
+    spans: Arc<Mutex<Vec<OtelSpan>>>,
+  ) -> Self {
+    Self {
+      endpoint: endpoint.trim_end_matches('/').into(),
+      protocol,
+      logs,
+      spans,
+    }
+  }
+
+  /// Checks that the endpoint can be used with the protocol.
+  pub fn is_valid_endpoint(endpoint: &str, protocol: OtelProtocol) -> bool {
+    match protocol {
+      OtelProtocol::HttpProtobuf => matches!(
+        network::Endpoint::parse(endpoint),
+        Some(network::Endpoint::Http { .. })
+      ),
+      OtelProtocol::Grpc => tonic::transport::Endpoint::from_shared(endpoint.to_string()).is_ok(),
+    }
+  }
+
+  /// Exports every second, the gRPC client is built right away and reused by every batch.
+  pub fn init(self, workers: &Workers) -> Result<(), Error> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .map_err(Error::Runtime)?;
+    let clients = self.connect(&runtime)?;
+
+    workers.spawn(OtelWorker {
+      exporter: self,
+      runtime,
+      clients: Mutex::new(clients),
+    });
+
+    Ok(())
+  }
+
+  /// The gRPC channel connects on the first export and reconnects by itself when it's lost.
+  fn connect(&self, runtime: &tokio::runtime::Runtime) -> Result<Option<GrpcClients>, Error> {
+    if self.protocol != OtelProtocol::Grpc {
+      return Ok(None);
+    }
+
+    // The channel spawns its worker into the runtime that drives the exports
+    let _guard = runtime.enter();
+    let channel = tonic::transport::Endpoint::from_shared(self.endpoint.clone())
+      .map_err(Error::GrpcConnect)?
+      .connect_lazy();
+
+    Ok(Some(GrpcClients {
+      logs: LogsServiceClient::new(channel.clone()),
+      spans: TraceServiceClient::new(channel),
+    }))
+  }
+
+  // The export is best effort: a batch that the collector doesn't accept is reported and dropped
+  fn export(&self, runtime: &tokio::runtime::Runtime, mut clients: Option<&mut GrpcClients>) {
+    let logs = {
+      let mut buffer = self.logs.lock().unwrap();
+
+      std::mem::take(&mut *buffer)
+    };
+
+    let spans = {
+      let mut buffer = self.spans.lock().unwrap();
+
+      std::mem::take(&mut *buffer)
+    };
+
+    if !logs.is_empty() {
+      if let Err(error) = self.export_logs(runtime, clients.as_deref_mut(), logs) {
+        super::report_error(error);
+      }
+    }
+
+    if !spans.is_empty() {
+      if let Err(error) = self.export_spans(runtime, clients, spans) {
+        super::report_error(error);
+      }
+    }
+  }
+
+  fn export_logs(
+    &self,
+    runtime: &tokio::runtime::Runtime,
+    clients: Option<&mut GrpcClients>,
+    logs: Vec<OtelLog>,
+  ) -> Result<(), Error> {
+    let request = ExportLogsServiceRequest {
+      resource_logs: vec![ResourceLogs {
+        resource: Some(get_resource()),
+        scope_logs: vec![ScopeLogs {
+          scope: Some(get_scope()),
+          log_records: logs.into_iter().map(to_log_record).collect(),
+          ..Default::default()
+        }],
+        ..Default::default()
+      }],
+    };
+
+    match clients {
+      None => self.post("/v1/logs", request.encode_to_vec()),
+      Some(clients) => runtime.block_on(async {
+        clients
+          .logs
+          .export(request)
+          .await
+          .map_err(|e| Error::Grpc(Box::new(e)))?;
+
+        Ok(())
+      }),
+    }
+  }
+
+  fn export_spans(
+    &self,
+    runtime: &tokio::runtime::Runtime,
+    clients: Option<&mut GrpcClients>,
+    spans: Vec<OtelSpan>,
+  ) -> Result<(), Error> {
+    let request = ExportTraceServiceRequest {
+      resource_spans: vec![ResourceSpans {
+        resource: Some(get_resource()),
+        scope_spans: vec![ScopeSpans {
+          scope: Some(get_scope()),
+          spans: spans.into_iter().map(to_span).collect(),
+          ..Default::default()
+        }],
+        ..Default::default()
+      }],
+    };
+
+    match clients {
+      None => self.post("/v1/traces", request.encode_to_vec()),
+      Some(clients) => runtime.block_on(async {
+        clients
+          .spans
+          .export(request)
+          .await
+          .map_err(|e| Error::Grpc(Box::new(e)))?;
+
+        Ok(())
+      }),
+    }
+  }
+
+  fn post(&self, signal_path: &str, body: Vec<u8>) -> Result<(), Error> {
+    let endpoint = format!("{}{}", self.endpoint, signal_path);
+
+    match network::Endpoint::parse(&endpoint) {
+      Some(network::Endpoint::Http { address, path }) => {
+        network::post(&address, &path, "application/x-protobuf", &body).map_err(Error::Http)
+      }
+      _ => unreachable!("the endpoint is validated when the logger is initialized"),
+    }
+  }
+}
+
+impl Worker for OtelWorker {
+  fn work(&self) {
+    let mut clients = self.clients.lock().unwrap();
+
+    self.exporter.export(&self.runtime, clients.as_mut());
+  }
+}
+
+impl OtelSpan {
+  pub fn new(
+    name: &str,
+    target: &str,
+    trace_id: Option<[u8; 16]>,
+    parent_span_id: Option<[u8; 8]>,
+  ) -> Self {
+    let trace_id = trace_id.unwrap_or_else(|| {
+      let mut id = [0; 16];
+      id[..8].copy_from_slice(&random_u64().to_be_bytes());
+      id[8..].copy_from_slice(&random_u64().to_be_bytes());
+      id
+    });
+
+    Self {
+      trace_id,
+      span_id: random_u64().to_be_bytes(),
+      parent_span_id,
+      name: name.into(),
+      target: target.into(),
+      start_time: now_nanos(),
+      end_time: 0,
+      attributes: Vec::new(),
+    }
+  }
+}
+
+impl tracing::field::Visit for SpanAttributes<'_> {
+  fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+    self.0.push((field.name().into(), format!("{:?}", value)));
+  }
+
+  fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+    self.0.push((field.name().into(), value.into()));
+  }
+}
+
+pub fn now_nanos() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_nanos() as u64)
+    .unwrap_or(0)
+}
+
+fn to_log_record(otel_log: OtelLog) -> LogRecord {
+  let OtelLog {
+    log,
+    target,
+    trace_id,
+    span_id,
+    attributes: fields,
+  } = otel_log;
+
+  let (severity_number, severity_text) = match log.level {
+    LogLevel::Error => (17, "ERROR"),
+    LogLevel::Warn => (13, "WARN"),
+    LogLevel::Info => (9, "INFO"),
+    LogLevel::Debug => (5, "DEBUG"),
+    LogLevel::Trace => (1, "TRACE"),
+    LogLevel::Off => (0, ""),
+  };
+
+  let mut attributes = vec![to_key_value("code.namespace", target)];
+  if let Some(category) = log.category {
+    attributes.push(to_key_value("category", category));
+  }
+  if let Some(stopwatch) = log.stopwatch {
+    attributes.push(to_key_value("stopwatch", stopwatch));
+  }
+  if let Some(error) = log.error {
+    attributes.push(to_key_value("exception.message", error));
+  }
+  attributes.extend(
+    fields
+      .into_iter()
+      .map(|(key, value)| to_key_value(&key, value)),
+  );
+
+  let time = log.timestamp as u64 * 1_000_000;
+
+  LogRecord {
+    time_unix_nano: time,
+    observed_time_unix_nano: time,
+    severity_number,
+    severity_text: severity_text.into(),
+    body: log.message.map(to_any_value),
+    attributes,
+    trace_id: trace_id.map(|i| i.to_vec()).unwrap_or_default(),
+    span_id: span_id.map(|i| i.to_vec()).unwrap_or_default(),
+    ..Default::default()
+  }
+}
+
+fn to_span(span: OtelSpan) -> Span {
+  let mut attributes = vec![to_key_value("code.namespace", span.target)];
+  attributes.extend(
+    span
+      .attributes
+      .into_iter()
+      .map(|(key, value)| to_key_value(&key, value)),
+  );
+
+  Span {
+    trace_id: span.trace_id.to_vec(),
+    span_id: span.span_id.to_vec(),
+    parent_span_id: span.parent_span_id.map(|i| i.to_vec()).unwrap_or_default(),
+    name: span.name,
+    kind: SpanKind::Internal as i32,
+    start_time_unix_nano: span.start_time,
+    end_time_unix_nano: span.end_time,
+    attributes,
+    ..Default::default()
+  }
+}
+
+fn get_resource() -> Resource {
+  Resource {
+    attributes: vec![to_key_value("service.name", get_app_name())],
+    ..Default::default()
+  }
+}
+
+fn get_scope() -> InstrumentationScope {
+  InstrumentationScope {
+    name: env!("CARGO_PKG_NAME").into(),
+    version: env!("CARGO_PKG_VERSION").into(),
+    ..Default::default()
+  }
+}
+
+fn to_key_value(key: &str, value: String) -> KeyValue {
+  KeyValue {
+    key: key.into(),
+    value: Some(to_any_value(value)),
+  }
+}
+
+fn to_any_value(value: String) -> AnyValue {
+  AnyValue {
+    value: Some(Value::StringValue(value)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{net::SocketAddr, thread};
+
+  use opentelemetry_proto::tonic::collector::{
+    logs::v1::{
+      logs_service_server::{LogsService, LogsServiceServer},
+      ExportLogsServiceResponse,
+    },
+    trace::v1::{
+      trace_service_server::{TraceService, TraceServiceServer},
+      ExportTraceServiceResponse,
+    },
+  };
+  use tonic::{transport::server::TcpIncoming, Request, Response, Status};
+
+  use super::*;
+  use crate::logger::test_support;
+
+  // The requests along with the address of the client that sent them
+  type Received<T> = Arc<Mutex<Vec<(Option<SocketAddr>, T)>>>;
+
+  #[derive(Debug, Clone, Default)]
+  struct Collector {
+    logs: Received<ExportLogsServiceRequest>,
+    spans: Received<ExportTraceServiceRequest>,
+  }
+
+  #[tonic::async_trait]
+  impl LogsService for Collector {
+    async fn export(
+      &self,
+      request: Request<ExportLogsServiceRequest>,
+    ) -> Result<Response<ExportLogsServiceResponse>, Status> {
+      let remote_addr = request.remote_addr();
+      let mut logs = self.logs.lock().unwrap();
+      logs.push((remote_addr, request.into_inner()));
+
+      Ok(Response::new(ExportLogsServiceResponse::default()))
+    }
+  }
+
+  #[tonic::async_trait]
+  impl TraceService for Collector {
+    async fn export(
+      &self,
+      request: Request<ExportTraceServiceRequest>,
+    ) -> Result<Response<ExportTraceServiceResponse>, Status> {
+      let remote_addr = request.remote_addr();
+      let mut spans = self.spans.lock().unwrap();
+      spans.push((remote_addr, request.into_inner()));
+
+      Ok(Response::new(ExportTraceServiceResponse::default()))
+    }
+  }
+
+  fn start_collector() -> (String, Collector) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let collector = Collector::default();
+    let service = collector.clone();
+
+    thread::spawn(move || {
+      let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+      runtime.block_on(async move {
+        let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+        let incoming = TcpIncoming::from_listener(listener, true, None).unwrap();
+
+        tonic::transport::Server::builder()
+          .add_service(LogsServiceServer::new(service.clone()))
+          .add_service(TraceServiceServer::new(service))
+          .serve_with_incoming(incoming)
+          .await
+          .unwrap();
+      });
+    });
+
+    (format!("http://{}", address), collector)
+  }
+
+  fn get_log(message: &str) -> OtelLog {
+    OtelLog {
+      log: FileLog {
+        timestamp: 1_700_000_000_123,
+        level: LogLevel::Warn,
+        category: Some("DB".into()),
+        stopwatch: Some("1.2ms".into()),
+        error: Some("error: timed out".into()),
+        ..test_support::get_log(message)
+      },
+      target: "app::db".into(),
+      trace_id: Some([1; 16]),
+      span_id: Some([2; 8]),
+      attributes: vec![("table".into(), "users".into())],
+    }
+  }
+
+  fn get_exporter(endpoint: &str, protocol: OtelProtocol) -> OtelExporter {
+    OtelExporter::new(
+      endpoint,
+      protocol,
+      Arc::new(Mutex::new(Vec::new())),
+      Arc::new(Mutex::new(Vec::new())),
+    )
+  }
+
+  fn get_attribute<'a>(attributes: &'a [KeyValue], key: &str) -> Option<&'a str> {
+    attributes.iter().find(|a| a.key == key).and_then(|a| {
+      match a.value.as_ref()?.value.as_ref()? {
+        Value::StringValue(value) => Some(value.as_str()),
+        _ => None,
+      }
+    })
+  }
+
+  #[test]
+  fn exports_the_logs_and_spans_over_grpc() {
+    let (endpoint, collector) = start_collector();
+    let exporter = get_exporter(&endpoint, OtelProtocol::Grpc);
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .unwrap();
+    let mut clients = exporter.connect(&runtime).unwrap();
+
+    let mut span = OtelSpan::new("query", "app::db", Some([1; 16]), None);
+    span.attributes.push(("table".into(), "users".into()));
+    span.end_time = span.start_time + 1;
+
+    exporter.logs.lock().unwrap().push(get_log("slow query"));
+    exporter.spans.lock().unwrap().push(span.clone());
+    exporter.export(&runtime, clients.as_mut());
+
+    let logs = collector.logs.lock().unwrap().clone();
+    let record = &logs[0].1.resource_logs[0].scope_logs[0].log_records[0];
+    assert_eq!(record.severity_number, 13);
+    assert_eq!(record.severity_text, "WARN");
+    assert_eq!(record.time_unix_nano, 1_700_000_000_123_000_000);
+    assert_eq!(record.body, Some(to_any_value("slow query".into())));
+    assert_eq!(record.trace_id, [1; 16]);
+    assert_eq!(record.span_id, [2; 8]);
+    assert_eq!(get_attribute(&record.attributes, "category"), Some("DB"));
+    assert_eq!(
+      get_attribute(&record.attributes, "stopwatch"),
+      Some("1.2ms")
+    );
+    assert_eq!(
+      get_attribute(&record.attributes, "exception.message"),
+      Some("error: timed out")
+    );
+    assert_eq!(
+      get_attribute(&record.attributes, "code.namespace"),
+      Some("app::db")
+    );
+    assert_eq!(get_attribute(&record.attributes, "table"), Some("users"));
+
+    let spans = collector.spans.lock().unwrap().clone();
+    let exported_span = &spans[0].1.resource_spans[0].scope_spans[0].spans[0];
+    assert_eq!(exported_span.name, "query");
+    assert_eq!(exported_span.trace_id, [1; 16]);
+    assert_eq!(exported_span.span_id, span.span_id);
+    assert!(exported_span.parent_span_id.is_empty());
+    assert_eq!(exported_span.end_time_unix_nano, span.end_time);
+    assert_eq!(
+      get_attribute(&exported_span.attributes, "table"),
+      Some("users")
+    );
+
+    // The next batch goes through the same connection
+    exporter.logs.lock().unwrap().push(get_log("slower query"));
+    exporter.export(&runtime, clients.as_mut());
+
+    let logs = collector.logs.lock().unwrap().clone();
+    assert_eq!(logs.len(), 2);
+    assert!(logs[0].0.is_some());
+    assert_eq!(logs[0].0, logs[1].0);
+    assert_eq!(logs[0].0, spans[0].0);
+  }
+
+  #[test]
+  fn defaults_to_the_port_of_the_protocol() {
+    assert_eq!(
+      OtelProtocol::HttpProtobuf.default_endpoint(),
+      "http://localhost:4318"
+    );
+    assert_eq!(
+      OtelProtocol::Grpc.default_endpoint(),
+      "http://localhost:4317"
+    );
+  }
+
+  #[test]
+  fn posts_protobuf_over_http() {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let server = thread::spawn(move || {
+      let (mut stream, _) = listener.accept().unwrap();
+      let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+      let mut request_line = String::new();
+      reader.read_line(&mut request_line).unwrap();
+
+      let mut content_length = 0;
+      loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if let Some(length) = line.strip_prefix("Content-Length: ") {
+          content_length = length.trim().parse().unwrap();
+        }
+        if line == "\r\n" {
+          break;
+        }
+      }
+
+      let mut body = vec![0; content_length];
+      reader.read_exact(&mut body).unwrap();
+      stream.write_all(b"HTTP/1.1 200 OK\r\n\r\n").unwrap();
+
+      (
+        request_line,
+        ExportLogsServiceRequest::decode(&body[..]).unwrap(),
+      )
+    });
+
+    let exporter = get_exporter(&format!("http://{}/", address), OtelProtocol::HttpProtobuf);
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .build()
+      .unwrap();
+    assert!(exporter.connect(&runtime).unwrap().is_none());
+
+    exporter.logs.lock().unwrap().push(get_log("posted"));
+    exporter.export(&runtime, None);
+
+    let (request_line, request) = server.join().unwrap();
+    assert_eq!(request_line, "POST /v1/logs HTTP/1.1\r\n");
+    assert_eq!(
+      request.resource_logs[0].scope_logs[0].log_records[0].body,
+      Some(to_any_value("posted".into()))
+    );
+  }
+}
@@ -0,0 +1,82 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use std::{backtrace::Backtrace, panic::PanicHookInfo};
+
+use super::{file, FileLog, Layer, LogLevel};
+
+/// Logs the panics as errors with the `PANIC` category.
+#[derive(Debug, Clone, Default)]
+pub struct PanicHook {
+  pub(super) enable: bool,
+  pub(super) backtrace: bool,
+}
+
+/// Installs a hook that logs the panic and dumps the ring buffer, then runs the previous hook.
+///
+/// The file logger is flushed before the previous hook runs, since it may abort the process.
+pub fn install(panic_hook: PanicHook, layer: Layer, file_logger: Option<file::FileLogger>) {
+  let previous_hook = std::panic::take_hook();
+
+  std::panic::set_hook(Box::new(move |info| {
+    let message = get_panic_message(info, panic_hook.backtrace);
+    let ring_buffer = &layer.ring_buffer;
+
+    match (panic_hook.enable, ring_buffer.enable) {
+      (true, _) => layer.log_panic(message),
+      (false, true) => {
+        ring_buffer.push(FileLog {
+          timestamp: chrono::Utc::now().timestamp_millis(),
+          level: LogLevel::Error,
+          category: Some("PANIC".into()),
+          message: Some(message),
+          stopwatch: None,
+          error: None,
+          sample_rate: None,
+        });
+
+        let _ = ring_buffer.dump();
+      }
+      (false, false) => {}
+    }
+
+    // The panic may come from the file logger itself, which still holds its writer
+    if let Some(file_logger) = &file_logger {
+      let _ = file_logger.try_flush();
+    }
+
+    previous_hook(info)
+  }));
+}
+
+/// Describes the panic like the default hook does.
+fn get_panic_message(info: &PanicHookInfo, backtrace: bool) -> String {
+  let payload = info
+    .payload()
+    .downcast_ref::<&str>()
+    .map(|p| p.to_string())
+    .or_else(|| info.payload().downcast_ref::<String>().cloned())
+    .unwrap_or("Box<dyn Any>".into());
+
+  let location = info
+    .location()
+    .map(|l| format!(" at {}:{}:{}", l.file(), l.line(), l.column()))
+    .unwrap_or_default();
+
+  let thread = std::thread::current();
+  let thread_name = thread.name().unwrap_or("<unnamed>");
+
+  let mut result = format!(
+    "thread '{}' panicked{}:\n{}",
+    thread_name, location, payload
+  );
+
+  if backtrace {
+    result.push_str(&format!(
+      "\nstack backtrace:\n{}",
+      Backtrace::force_capture()
+    ));
+  }
+
+  result
+}
@@ -0,0 +1,283 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use std::sync::OnceLock;
+
+use hmac::{Hmac, Mac};
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use sha2::Sha256;
+
+use super::{random_u64, Error, FileLog};
+
+const FULL_MASK: &str = "[REDACTED]";
+
+/// How the sensitive values are hidden.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Masking {
+  /// Replaces the value with `[REDACTED]`.
+  #[default]
+  Full,
+  /// Keeps a few characters at both ends, like `ab******yz`.
+  Partial,
+  /// Replaces the value with a short HMAC-SHA256 digest, so equal values can still be
+  /// correlated.
+  ///
+  /// The key is random for every process, so the digests of two runs can't be compared, and
+  /// the values can't be guessed by hashing the likely ones.
+  Hashed,
+}
+
+/// The redaction rules set on the builder.
+#[derive(Debug, Clone, Default)]
+pub struct RedactionRules {
+  pub(super) fields: Vec<(String, Masking)>,
+  pub(super) patterns: Vec<(String, Masking)>,
+}
+
+/// Masks the sensitive values in the text fields of the logs.
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+  rules: Vec<(Regex, Masking, bool)>,
+}
+
+impl Redactor {
+  pub fn new(rules: &RedactionRules) -> Result<Self, Error> {
+    let mut result = Vec::new();
+
+    // Matches things like 'password=1234', 'token: abcd' or '"secret": "abcd"'
+    for (field, masking) in &rules.fields {
+      let pattern = format!(
+        r#"(?i)(\b{}\b"?\s*[=:]\s*)("[^"]*"|'[^']*'|[^\s,;&"']+)"#,
+        regex::escape(field)
+      );
+      let regex = Regex::new(&pattern).map_err(|e| Error::RedactionPattern(pattern, e))?;
+
+      result.push((regex, *masking, true));
+    }
+
+    for (pattern, masking) in &rules.patterns {
+      let regex = Regex::new(pattern).map_err(|e| Error::RedactionPattern(pattern.clone(), e))?;
+
+      result.push((regex, *masking, false));
+    }
+
+    Ok(Self { rules: result })
+  }
+
+  pub fn is_enabled(&self) -> bool {
+    !self.rules.is_empty()
+  }
+
+  pub fn redact(&self, log: &mut FileLog) {
+    let fields = [
+      &mut log.category,
+      &mut log.message,
+      &mut log.stopwatch,
+      &mut log.error,
+    ];
+
+    for value in fields.into_iter().flatten() {
+      *value = self.redact_str(value);
+    }
+  }
+
+  fn redact_str(&self, string: &str) -> String {
+    let mut result = string.to_string();
+
+    for (regex, masking, is_field) in &self.rules {
+      result = regex
+        .replace_all(&result, |captures: &Captures| match is_field {
+          true => format!("{}{}", &captures[1], mask_quoted(&captures[2], *masking)),
+          false => mask(&captures[0], *masking),
+        })
+        .into_owned();
+    }
+
+    result
+  }
+}
+
+fn mask_quoted(value: &str, masking: Masking) -> String {
+  let quote = value
+    .chars()
+    .next()
+    .filter(|c| matches!(c, '"' | '\'') && value.len() >= 2);
+
+  match quote {
+    Some(quote) => format!(
+      "{}{}{}",
+      quote,
+      mask(&value[1..value.len() - 1], masking),
+      quote
+    ),
+    None => mask(value, masking),
+  }
+}
+
+fn mask(value: &str, masking: Masking) -> String {
+  match masking {
+    Masking::Full => FULL_MASK.into(),
+    Masking::Partial => {
+      let chars = value.chars().collect::<Vec<_>>();
+      // Short values are hidden completely, they would be too easy to guess
+      let keep = (chars.len() / 4).min(4);
+
+      match keep {
+        0 => "*".repeat(chars.len().max(1)),
+        _ => format!(
+          "{}{}{}",
+          chars[..keep].iter().collect::<String>(),
+          "*".repeat(chars.len() - keep * 2),
+          chars[chars.len() - keep..].iter().collect::<String>()
+        ),
+      }
+    }
+    Masking::Hashed => {
+      let mut mac = Hmac::<Sha256>::new_from_slice(get_hash_key()).unwrap();
+      mac.update(value.as_bytes());
+
+      let hex = mac.finalize().into_bytes()[..8]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+
+      format!("[hmac:{}]", hex)
+    }
+  }
+}
+
+/// The key of the hashed values, drawn once per process.
+fn get_hash_key() -> &'static [u8; 32] {
+  static KEY: OnceLock<[u8; 32]> = OnceLock::new();
+
+  KEY.get_or_init(|| {
+    let mut key = [0; 32];
+
+    if getrandom::getrandom(&mut key).is_err() {
+      for chunk in key.chunks_mut(8) {
+        chunk.copy_from_slice(&random_u64().to_le_bytes());
+      }
+    }
+
+    key
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use sha2::Digest;
+
+  use super::*;
+  use crate::logger::test_support::get_log;
+
+  fn get_redactor(fields: &[(&str, Masking)], patterns: &[(&str, Masking)]) -> Redactor {
+    let to_rules = |rules: &[(&str, Masking)]| {
+      rules
+        .iter()
+        .map(|(r, m)| (r.to_string(), *m))
+        .collect::<Vec<_>>()
+    };
+
+    Redactor::new(&RedactionRules {
+      fields: to_rules(fields),
+      patterns: to_rules(patterns),
+    })
+    .unwrap()
+  }
+
+  #[test]
+  fn fields_are_masked_in_every_form() {
+    let redactor = get_redactor(&[("password", Masking::Full)], &[]);
+
+    assert_eq!(
+      redactor.redact_str("login password=hunter2 ok"),
+      "login password=[REDACTED] ok"
+    );
+    assert_eq!(
+      redactor.redact_str("PASSWORD: hunter2; next"),
+      "PASSWORD: [REDACTED]; next"
+    );
+    assert_eq!(
+      redactor.redact_str(r#"{"password": "hunter 2"}"#),
+      r#"{"password": "[REDACTED]"}"#
+    );
+    assert_eq!(
+      redactor.redact_str("password='hunter2'"),
+      "password='[REDACTED]'"
+    );
+    assert_eq!(redactor.redact_str("passwords=1"), "passwords=1");
+  }
+
+  #[test]
+  fn patterns_mask_every_match() {
+    let redactor = get_redactor(&[], &[(r"\d{4}-\d{4}", Masking::Full)]);
+
+    assert_eq!(
+      redactor.redact_str("cards 1234-5678 and 8765-4321"),
+      "cards [REDACTED] and [REDACTED]"
+    );
+  }
+
+  #[test]
+  fn partial_masking_keeps_the_ends() {
+    assert_eq!(mask("abcdefghijkl", Masking::Partial), "abc******jkl");
+    assert_eq!(mask("abc", Masking::Partial), "***");
+    assert_eq!(mask("", Masking::Partial), "*");
+  }
+
+  #[test]
+  fn hashed_masking_correlates_equal_values() {
+    let first = mask("hunter2", Masking::Hashed);
+
+    assert_eq!(first, mask("hunter2", Masking::Hashed));
+    assert_ne!(first, mask("hunter3", Masking::Hashed));
+    assert!(first.starts_with("[hmac:") && first.len() == "[hmac:]".len() + 16);
+  }
+
+  #[test]
+  fn hashed_masking_isnt_the_plain_digest() {
+    let digest = sha2::Sha256::digest(b"hunter2");
+    let hex = digest[..8]
+      .iter()
+      .map(|b| format!("{:02x}", b))
+      .collect::<String>();
+
+    assert!(!mask("hunter2", Masking::Hashed).contains(&hex));
+  }
+
+  #[test]
+  fn redact_masks_every_text_field() {
+    let redactor = get_redactor(&[("token", Masking::Full)], &[]);
+    let mut log = FileLog {
+      category: Some("token=abcd".into()),
+      stopwatch: Some("token=abcd 12ms".into()),
+      error: Some("error: token=abcd is expired".into()),
+      ..get_log("refreshing token=abcd")
+    };
+
+    redactor.redact(&mut log);
+
+    assert_eq!(log.category.as_deref(), Some("token=[REDACTED]"));
+    assert_eq!(log.message.as_deref(), Some("refreshing token=[REDACTED]"));
+    assert_eq!(log.stopwatch.as_deref(), Some("token=[REDACTED] 12ms"));
+    assert_eq!(
+      log.error.as_deref(),
+      Some("error: token=[REDACTED] is expired")
+    );
+  }
+
+  #[test]
+  fn invalid_patterns_fail() {
+    let rules = RedactionRules {
+      fields: Vec::new(),
+      patterns: vec![("(unclosed".into(), Masking::Full)],
+    };
+
+    assert!(matches!(
+      Redactor::new(&rules),
+      Err(Error::RedactionPattern(pattern, _)) if pattern == "(unclosed"
+    ));
+  }
+}
@@ -0,0 +1,178 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use std::{
+  collections::VecDeque,
+  fs::{File, OpenOptions},
+  io::{ErrorKind, Write},
+  path::Path,
+  sync::{Arc, Mutex, MutexGuard},
+};
+
+use csv::Writer;
+use thiserror::Error;
+
+use super::{file, FileLog, LogLevel};
+
+/// Keeps the last logs in memory, to dump them when something goes wrong.
+///
+/// It has its own level, so it can keep the logs below the level of the logger. The dumps are CSV files with the columns of [`FileLog`], or the same CSV on stderr.
+#[derive(Debug, Clone)]
+pub struct RingBuffer {
+  pub(super) enable: bool,
+  pub(super) level: LogLevel,
+  pub(super) capacity: usize,
+  pub(super) dump_path: Option<String>,
+  records: Arc<Mutex<VecDeque<FileLog>>>,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+  #[error("couldn't create the dump file")]
+  CreateFile(std::io::Error),
+  #[error("couldn't serialize the log")]
+  Serialize(csv::Error),
+  #[error("couldn't write the dump")]
+  Write(std::io::Error),
+}
+
+impl Default for RingBuffer {
+  fn default() -> Self {
+    Self {
+      enable: false,
+      level: LogLevel::Debug,
+      capacity: 1000,
+      dump_path: None,
+      records: Arc::new(Mutex::new(VecDeque::new())),
+    }
+  }
+}
+
+impl RingBuffer {
+  pub fn is_level_enabled(&self, level: LogLevel) -> bool {
+    self.enable && self.level >= level
+  }
+
+  pub fn push(&self, log: FileLog) {
+    let mut records = self.lock();
+
+    if records.len() >= self.capacity {
+      records.pop_front();
+    }
+
+    records.push_back(log);
+  }
+
+  /// Writes the buffered logs and empties the buffer, so the next dump only has newer logs.
+  pub fn dump(&self) -> Result<(), Error> {
+    let records = std::mem::take(&mut *self.lock());
+
+    self.write(records)
+  }
+
+  // It's also used while panicking, when a poisoned lock still has the logs
+  fn lock(&self) -> MutexGuard<'_, VecDeque<FileLog>> {
+    self.records.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  fn write(&self, records: VecDeque<FileLog>) -> Result<(), Error> {
+    if records.is_empty() {
+      return Ok(());
+    }
+
+    match &self.dump_path {
+      Some(dump_path) => {
+        let dir_path = file::get_dir_path(Path::new(dump_path));
+        std::fs::create_dir_all(&dir_path).map_err(Error::CreateFile)?;
+
+        write_records(Writer::from_writer(create_dump_file(&dir_path)?), records)
+      }
+      None => write_records(Writer::from_writer(std::io::stderr()), records),
+    }
+  }
+}
+
+fn write_records<W: Write>(mut writer: Writer<W>, records: VecDeque<FileLog>) -> Result<(), Error> {
+  for record in records {
+    writer.serialize(record).map_err(Error::Serialize)?;
+  }
+
+  writer.flush().map_err(Error::Write)
+}
+
+// Several dumps can happen within the same millisecond, like an error followed by a panic
+fn create_dump_file(dir_path: &Path) -> Result<File, Error> {
+  let timestamp = chrono::Utc::now().format("%Y%m%d-%H%M%S%.3f").to_string();
+
+  for idx in 0.. {
+    let name = match idx {
+      0 => format!("{}-dump.csv", timestamp),
+      _ => format!("{}-{}-dump.csv", timestamp, idx),
+    };
+
+    match OpenOptions::new()
+      .write(true)
+      .create_new(true)
+      .open(dir_path.join(name))
+    {
+      Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+      result => return result.map_err(Error::CreateFile),
+    }
+  }
+
+  unreachable!()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::logger::test_support::{get_log, TempDir};
+
+  fn get_messages(ring_buffer: &RingBuffer) -> Vec<String> {
+    ring_buffer
+      .lock()
+      .iter()
+      .map(|l| l.message.clone().unwrap())
+      .collect()
+  }
+
+  #[test]
+  fn push_keeps_the_newest_logs() {
+    let ring_buffer = RingBuffer {
+      capacity: 2,
+      ..Default::default()
+    };
+
+    for message in ["first", "second", "third"] {
+      ring_buffer.push(get_log(message));
+    }
+
+    assert_eq!(get_messages(&ring_buffer), ["second", "third"]);
+  }
+
+  #[test]
+  fn dump_empties_the_buffer() {
+    let dir_path = TempDir::new("ring");
+
+    let ring_buffer = RingBuffer {
+      dump_path: Some(dir_path.to_string_lossy().to_string()),
+      ..Default::default()
+    };
+
+    ring_buffer.push(get_log("before the dump"));
+    ring_buffer.dump().unwrap();
+
+    assert!(get_messages(&ring_buffer).is_empty());
+    assert_eq!(std::fs::read_dir(&dir_path).unwrap().count(), 1);
+  }
+
+  #[test]
+  fn level_is_only_enabled_with_the_buffer() {
+    let mut ring_buffer = RingBuffer::default();
+    assert!(!ring_buffer.is_level_enabled(LogLevel::Error));
+
+    ring_buffer.enable = true;
+    assert!(ring_buffer.is_level_enabled(LogLevel::Debug));
+    assert!(!ring_buffer.is_level_enabled(LogLevel::Trace));
+  }
+}
@@ -0,0 +1,166 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use std::{
+  collections::HashMap,
+  sync::{Arc, Mutex},
+};
+
+use serde::Deserialize;
+
+use super::{random_u64, LogLevel};
+
+// The module is `None` for the sampling of the whole level
+type CounterKey = (Option<String>, LogLevel);
+
+/// How many of the logs of a level are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Sampling {
+  /// Keeps each log with the given probability, between `0.0` and `1.0`.
+  Probability(f64),
+  /// Keeps the first log of every `n`.
+  EveryNth(u32),
+}
+
+/// Drops part of the logs of the verbose levels, never the errors or the warnings.
+#[derive(Debug, Clone, Default)]
+pub struct Sampler {
+  pub(super) level_samplings: HashMap<LogLevel, Sampling>,
+  pub(super) module_samplings: HashMap<(String, LogLevel), Option<Sampling>>,
+  counters: Arc<Mutex<HashMap<CounterKey, u64>>>,
+}
+
+impl Sampling {
+  /// The fraction of the logs that are kept.
+  pub fn rate(&self) -> f64 {
+    match *self {
+      Sampling::Probability(probability) => probability.clamp(0.0, 1.0),
+      Sampling::EveryNth(n) => 1.0 / n.max(1) as f64,
+    }
+  }
+}
+
+impl Sampler {
+  pub fn is_enabled(&self) -> bool {
+    !self.level_samplings.is_empty() || self.module_samplings.values().any(|s| s.is_some())
+  }
+
+  /// Returns the rate the log was sampled at, or `None` when it's dropped.
+  ///
+  /// The logs that aren't sampled have a rate of `1.0`.
+  pub fn sample(&self, module: &str, level: LogLevel) -> Option<f64> {
+    if level <= LogLevel::Warn {
+      return Some(1.0);
+    }
+
+    let module_sampling = self
+      .module_samplings
+      .get(&(module.to_string(), level))
+      .map(|s| (Some(module.to_string()), *s));
+
+    let (key, sampling) = match module_sampling {
+      Some(module_sampling) => module_sampling,
+      None => (None, self.level_samplings.get(&level).copied()),
+    };
+
+    let Some(sampling) = sampling else {
+      return Some(1.0);
+    };
+
+    let keep = match sampling {
+      Sampling::Probability(probability) => (random_u64() as f64 / u64::MAX as f64) < probability,
+      Sampling::EveryNth(n) => {
+        let mut counters = self.counters.lock().unwrap();
+        let counter = counters.entry((key, level)).or_insert(0);
+        *counter += 1;
+
+        (*counter - 1).is_multiple_of(n.max(1) as u64)
+      }
+    };
+
+    keep.then(|| sampling.rate())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn get_sampler(level_samplings: &[(LogLevel, Sampling)]) -> Sampler {
+    Sampler {
+      level_samplings: level_samplings.iter().copied().collect(),
+      ..Default::default()
+    }
+  }
+
+  fn count_kept(sampler: &Sampler, module: &str, level: LogLevel, total: usize) -> usize {
+    (0..total)
+      .filter(|_| sampler.sample(module, level).is_some())
+      .count()
+  }
+
+  #[test]
+  fn every_nth_keeps_the_first_of_every_n() {
+    let sampler = get_sampler(&[(LogLevel::Debug, Sampling::EveryNth(3))]);
+
+    let kept = (0..7)
+      .map(|_| sampler.sample("app", LogLevel::Debug))
+      .collect::<Vec<_>>();
+
+    let rate = Some(1.0 / 3.0);
+    assert_eq!(kept, [rate, None, None, rate, None, None, rate]);
+  }
+
+  #[test]
+  fn every_nth_of_zero_keeps_everything() {
+    let sampler = get_sampler(&[(LogLevel::Debug, Sampling::EveryNth(0))]);
+
+    assert_eq!(count_kept(&sampler, "app", LogLevel::Debug, 5), 5);
+  }
+
+  #[test]
+  fn probability_keeps_all_or_nothing_at_the_ends() {
+    let sampler = get_sampler(&[
+      (LogLevel::Debug, Sampling::Probability(1.0)),
+      (LogLevel::Trace, Sampling::Probability(0.0)),
+    ]);
+
+    assert_eq!(count_kept(&sampler, "app", LogLevel::Debug, 100), 100);
+    assert_eq!(count_kept(&sampler, "app", LogLevel::Trace, 100), 0);
+  }
+
+  #[test]
+  fn errors_and_warnings_are_never_sampled_out() {
+    let sampler = get_sampler(&[
+      (LogLevel::Error, Sampling::Probability(0.0)),
+      (LogLevel::Warn, Sampling::EveryNth(10)),
+    ]);
+
+    assert_eq!(count_kept(&sampler, "app", LogLevel::Error, 10), 10);
+    assert_eq!(count_kept(&sampler, "app", LogLevel::Warn, 10), 10);
+  }
+
+  #[test]
+  fn module_samplings_override_the_level_ones() {
+    let mut sampler = get_sampler(&[(LogLevel::Debug, Sampling::EveryNth(2))]);
+    sampler
+      .module_samplings
+      .insert(("app::db".into(), LogLevel::Debug), None);
+    sampler.module_samplings.insert(
+      ("app::http".into(), LogLevel::Debug),
+      Some(Sampling::EveryNth(5)),
+    );
+
+    assert_eq!(count_kept(&sampler, "app::db", LogLevel::Debug, 10), 10);
+    assert_eq!(count_kept(&sampler, "app::http", LogLevel::Debug, 10), 2);
+    assert_eq!(count_kept(&sampler, "app", LogLevel::Debug, 10), 5);
+  }
+
+  #[test]
+  fn rate_is_the_kept_fraction() {
+    assert_eq!(Sampling::EveryNth(4).rate(), 0.25);
+    assert_eq!(Sampling::Probability(1.5).rate(), 1.0);
+    assert_eq!(Sampling::Probability(0.1).rate(), 0.1);
+  }
+}
@@ -0,0 +1,180 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use std::{os::unix::net::UnixDatagram, path::Path};
+
+use super::{get_app_name, Error, FileLog};
+
+// Enterprise number reserved for documentation (RFC 5612)
+const SD_ID: &str = "commonlib@32473";
+// The last facility of RFC 5424, 'local7'
+const MAX_FACILITY: u8 = 23;
+
+#[derive(Debug)]
+pub struct SyslogSink {
+  socket: UnixDatagram,
+  facility: u8,
+  hostname: String,
+  app_name: String,
+  proc_id: String,
+}
+
+impl SyslogSink {
+  pub fn connect(path: &Path, facility: u8) -> Result<Self, Error> {
+    if facility > MAX_FACILITY {
+      return Err(Error::SyslogFacility(facility));
+    }
+
+    let socket = UnixDatagram::unbound().map_err(Error::Syslog)?;
+    socket.connect(path).map_err(Error::Syslog)?;
+
+    Ok(Self {
+      socket,
+      facility,
+      hostname: get_hostname(),
+      app_name: get_app_name(),
+      proc_id: std::process::id().to_string(),
+    })
+  }
+
+  pub fn log(&self, log: &FileLog) {
+    let message = self.format(log);
+
+    // A logger has nowhere to report its own failures
+    let _ = self.socket.send(message.as_bytes());
+  }
+
+  fn format(&self, log: &FileLog) -> String {
+    let severity = log.level.to_syslog_severity().unwrap_or(7);
+    let priority = self.facility as u16 * 8 + severity as u16;
+
+    let timestamp = chrono::DateTime::from_timestamp_millis(log.timestamp)
+      .map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+      .unwrap_or("-".into());
+
+    let mut params = Vec::new();
+    if let Some(category) = &log.category {
+      params.push(format!("category=\"{}\"", escape_param(category)));
+    }
+    if let Some(error) = &log.error {
+      params.push(format!("error=\"{}\"", escape_param(error)));
+    }
+    if let Some(stopwatch) = &log.stopwatch {
+      params.push(format!("stopwatch=\"{}\"", escape_param(stopwatch)));
+    }
+
+    let structured_data = match params.is_empty() {
+      true => "-".into(),
+      false => format!("[{} {}]", SD_ID, params.join(" ")),
+    };
+
+    let message = log
+      .message
+      .as_ref()
+      .map(|m| format!(" {}", m))
+      .unwrap_or("".into());
+
+    format!(
+      "<{}>1 {} {} {} {} - {}{}",
+      priority, timestamp, self.hostname, self.app_name, self.proc_id, structured_data, message
+    )
+  }
+}
+
+fn escape_param(value: &str) -> String {
+  let mut result = String::with_capacity(value.len());
+
+  for char in value.chars() {
+    if matches!(char, '"' | '\\' | ']') {
+      result.push('\\');
+    }
+
+    result.push(char);
+  }
+
+  result
+}
+
+fn get_hostname() -> String {
+  std::fs::read_to_string("/proc/sys/kernel/hostname")
+    .or_else(|_| std::fs::read_to_string("/etc/hostname"))
+    .map(|h| h.trim().to_string())
+    .ok()
+    .filter(|h| !h.is_empty())
+    .unwrap_or("-".into())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::os::unix::net::UnixDatagram;
+
+  use super::*;
+  use crate::logger::{test_support, test_support::TempDir, LogLevel};
+
+  fn get_log() -> FileLog {
+    FileLog {
+      timestamp: 1_700_000_000_123,
+      level: LogLevel::Warn,
+      category: Some("DB".into()),
+      error: Some("broken \"pipe\" [os]".into()),
+      ..test_support::get_log("connection lost")
+    }
+  }
+
+  fn receive(name: &str, facility: u8, log: &FileLog) -> String {
+    let dir_path = TempDir::new(&format!("syslog-{}", name));
+    let path = dir_path.join("syslog.sock");
+    let server = UnixDatagram::bind(&path).unwrap();
+
+    let sink = SyslogSink::connect(&path, facility).unwrap();
+    sink.log(log);
+
+    let mut buffer = [0; 1024];
+    let len = server.recv(&mut buffer).unwrap();
+
+    String::from_utf8(buffer[..len].to_vec()).unwrap()
+  }
+
+  #[test]
+  fn sends_an_rfc_5424_frame() {
+    let frame = receive("frame", 1, &get_log());
+
+    let parts = frame.splitn(7, ' ').collect::<Vec<_>>();
+    assert_eq!(parts[0], "<12>1");
+    assert_eq!(parts[1], "2023-11-14T22:13:20.123Z");
+    assert_eq!(parts[3], get_app_name());
+    assert_eq!(parts[4], std::process::id().to_string());
+    assert_eq!(parts[5], "-");
+    assert_eq!(
+      parts[6],
+      "[commonlib@32473 category=\"DB\" error=\"broken \\\"pipe\\\" [os\\]\"] connection lost"
+    );
+  }
+
+  #[test]
+  fn maps_the_facility_and_severity_to_the_priority() {
+    let mut log = get_log();
+    log.level = LogLevel::Error;
+
+    assert!(receive("priority", 16, &log).starts_with("<131>1 "));
+  }
+
+  #[test]
+  fn rejects_a_facility_above_local7() {
+    assert!(matches!(
+      SyslogSink::connect(Path::new("/nonexistent"), 24),
+      Err(Error::SyslogFacility(24))
+    ));
+  }
+
+  #[test]
+  fn uses_a_nil_structured_data_without_fields() {
+    let log = FileLog {
+      category: None,
+      error: None,
+      ..get_log()
+    };
+
+    assert!(receive("nil", 1, &log).ends_with(" - - connection lost"));
+  }
+}
@@ -0,0 +1,24 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+//! Fixtures shared by the unit tests of the logger.
+
+#[path = "../../tests/support/mod.rs"]
+mod support;
+
+pub use support::TempDir;
+
+use super::{FileLog, LogLevel};
+
+/// An info log with just the message, the tests set the rest with the struct update syntax.
+pub fn get_log(message: &str) -> FileLog {
+  FileLog {
+    timestamp: chrono::Utc::now().timestamp_millis(),
+    level: LogLevel::Info,
+    category: None,
+    message: Some(message.into()),
+    stopwatch: None,
+    error: None,
+    sample_rate: None,
+  }
+}
@@ -0,0 +1,262 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+//! Utilities to assert on the logs emitted by the code under test.
+//!
+//! ```
+//! let _capture = commonlib::logger::testing::capture();
+//!
+//! tracing::warn!(category = "db", "retrying the query");
+//!
+//! commonlib::assert_logged!(level = Warn, category = "DB", message contains "retry");
+//! ```
+//!
+//! The captures are installed with [`tracing::subscriber::set_default`], so they only see the
+//! logs of the thread that created them: the logs of spawned threads, or of async tasks that run
+//! on other threads, aren't captured.
+
+use std::{
+  cell::RefCell,
+  sync::{Arc, Mutex},
+};
+
+use tracing::subscriber::DefaultGuard;
+use tracing_subscriber::layer::SubscriberExt;
+
+pub use crate::{assert_logged, assert_not_logged};
+
+#[cfg(feature = "logger-redaction")]
+use super::redactor::Redactor;
+use super::{Error, FileLog, Layer, LogLevel, Logger, Unlocked};
+
+thread_local! {
+  static CAPTURES: RefCell<Vec<Arc<Mutex<Vec<FileLog>>>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Collects the logs of the current thread until it's dropped.
+///
+/// The logs don't reach the console or any of the sinks. The logs of other threads aren't
+/// collected, even the ones of threads spawned while the capture is alive.
+pub struct Capture {
+  logs: Arc<Mutex<Vec<FileLog>>>,
+  _guard: DefaultGuard,
+}
+
+/// Describes the logs looked up by [`assert_logged!`] and [`assert_not_logged!`].
+#[derive(Debug, Default)]
+pub struct Matcher {
+  level: Option<LogLevel>,
+  fields: Vec<(String, bool, String)>,
+}
+
+/// Captures the logs of every level emitted by the current thread.
+pub fn capture() -> Capture {
+  Logger::new()
+    .set_level(LogLevel::Trace)
+    .capture()
+    .expect("a logger without redaction patterns can always capture")
+}
+
+/// Returns the logs collected by the innermost capture of the current thread.
+pub fn logs() -> Vec<FileLog> {
+  CAPTURES.with(|c| {
+    c.borrow()
+      .last()
+      .map(|l| l.lock().unwrap().clone())
+      .expect("there isn't any log capture in the current thread")
+  })
+}
+
+impl Logger<Unlocked> {
+  /// Captures the logs of the current thread that pass the level and module filters of this
+  /// logger.
+  ///
+  /// It fails if the redaction patterns aren't valid.
+  pub fn capture(self) -> Result<Capture, Error> {
+    let logs = Arc::new(Mutex::new(Vec::new()));
+
+    let subscriber = tracing_subscriber::registry().with(Layer {
+      level: self.level,
+      module_filters: self.module_filters,
+      file_logger: super::FileLogger {
+        enable: false,
+        ..self.file_logger
+      },
+      #[cfg(feature = "logger-network")]
+      network_logger: super::NetworkLogger {
+        enable: false,
+        ..self.network_logger
+      },
+      #[cfg(unix)]
+      syslog_logger: None,
+      #[cfg(unix)]
+      journald_logger: None,
+      #[cfg(feature = "otel")]
+      otel_exporter: super::OtelExporter {
+        enable: false,
+        ..self.otel_exporter
+      },
+      capture: Some(logs.clone()),
+      limiter: self.limiter,
+      sampler: self.sampler,
+      ring_buffer: super::ring::RingBuffer::default(),
+      metrics: self.metrics,
+      console_layout: self.console_layout,
+      #[cfg(feature = "logger-redaction")]
+      redactor: Redactor::new(&self.redaction)?,
+    });
+
+    let guard = tracing::subscriber::set_default(subscriber);
+    CAPTURES.with(|c| c.borrow_mut().push(logs.clone()));
+
+    Ok(Capture {
+      logs,
+      _guard: guard,
+    })
+  }
+}
+
+impl Capture {
+  pub fn logs(&self) -> Vec<FileLog> {
+    self.logs.lock().unwrap().clone()
+  }
+
+  pub fn clear(&self) {
+    self.logs.lock().unwrap().clear()
+  }
+}
+
+impl Drop for Capture {
+  fn drop(&mut self) {
+    CAPTURES.with(|c| {
+      c.borrow_mut().retain(|l| !Arc::ptr_eq(l, &self.logs));
+    });
+  }
+}
+
+impl Matcher {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn level(mut self, level: LogLevel) -> Self {
+    self.level = Some(level);
+    self
+  }
+
+  /// Matches one of `message`, `category`, `stopwatch` or `error`, either whole or partially.
+  ///
+  /// It panics with any other field, so a misspelled one doesn't pass `assert_not_logged!`.
+  pub fn field(mut self, name: &str, contains: bool, value: &str) -> Self {
+    if !matches!(
+      name,
+      "message" | "msg" | "category" | "cat" | "stopwatch" | "sw" | "error" | "err"
+    ) {
+      panic!(
+        "the log field '{}' is not valid, it must be one of 'message', 'category', 'stopwatch' or 'error'",
+        name
+      );
+    }
+
+    self.fields.push((name.into(), contains, value.into()));
+    self
+  }
+
+  pub fn matches(&self, log: &FileLog) -> bool {
+    if self.level.is_some_and(|l| l != log.level) {
+      return false;
+    }
+
+    self.fields.iter().all(
+      |(name, contains, value)| match (get_field(log, name), contains) {
+        (Some(field), true) => field.contains(value.as_str()),
+        (Some(field), false) => field == value,
+        (None, _) => false,
+      },
+    )
+  }
+
+  pub fn find(&self, logs: &[FileLog]) -> Option<usize> {
+    logs.iter().position(|l| self.matches(l))
+  }
+}
+
+impl std::fmt::Display for Matcher {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let mut conditions = Vec::new();
+
+    if let Some(level) = self.level {
+      conditions.push(format!("level = {:?}", level));
+    }
+
+    for (name, contains, value) in &self.fields {
+      let operator = if *contains { "contains" } else { "=" };
+      conditions.push(format!("{} {} {:?}", name, operator, value));
+    }
+
+    write!(f, "{}", conditions.join(", "))
+  }
+}
+
+fn get_field<'a>(log: &'a FileLog, name: &str) -> Option<&'a String> {
+  match name {
+    "message" | "msg" => log.message.as_ref(),
+    "category" | "cat" => log.category.as_ref(),
+    "stopwatch" | "sw" => log.stopwatch.as_ref(),
+    "error" | "err" => log.error.as_ref(),
+    _ => unreachable!("the fields are checked when they're added to the matcher"),
+  }
+}
+
+/// Asserts that the current log capture has a log matching every condition.
+///
+/// The conditions are `level = <LogLevel variant>` and `<field> = <value>` or
+/// `<field> contains <value>`, where the field is `message`, `category`, `stopwatch` or `error`.
+#[macro_export]
+macro_rules! assert_logged {
+  ($($key:ident $operator:tt $value:expr),+ $(,)?) => {{
+    let mut matcher = $crate::logger::testing::Matcher::new();
+    $(matcher = $crate::__assert_logged_rule!(matcher, $key $operator $value);)+
+    let logs = $crate::logger::testing::logs();
+
+    if matcher.find(&logs).is_none() {
+      panic!(
+        "no log matched '{}', the captured logs are: {:#?}",
+        matcher, logs
+      );
+    }
+  }};
+}
+
+/// Asserts that the current log capture doesn't have any log matching every condition.
+///
+/// Takes the same conditions as [`assert_logged!`].
+#[macro_export]
+macro_rules! assert_not_logged {
+  ($($key:ident $operator:tt $value:expr),+ $(,)?) => {{
+    let mut matcher = $crate::logger::testing::Matcher::new();
+    $(matcher = $crate::__assert_logged_rule!(matcher, $key $operator $value);)+
+    let logs = $crate::logger::testing::logs();
+
+    if let Some(idx) = matcher.find(&logs) {
+      panic!("a log matched '{}': {:#?}", matcher, logs[idx]);
+    }
+  }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __assert_logged_rule {
+  ($matcher:ident, level = $value:expr) => {{
+    #[allow(unused_imports)]
+    use $crate::logger::LogLevel::*;
+
+    $matcher.level($value)
+  }};
+  ($matcher:ident, $field:ident = $value:expr) => {
+    $matcher.field(stringify!($field), false, $value)
+  };
+  ($matcher:ident, $field:ident contains $value:expr) => {
+    $matcher.field(stringify!($field), true, $value)
+  };
+}
@@ -0,0 +1,180 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use std::{
+  fmt::Debug,
+  sync::{Arc, Condvar, Mutex},
+  time::Duration,
+};
+
+use async_std::task::JoinHandle;
+
+const INTERVAL: Duration = Duration::from_secs(1);
+
+/// A sink that sends its buffered logs in the background.
+pub trait Worker: Debug + Send + Sync + 'static {
+  /// Sends the logs buffered since the last time, the worker reports its own failures.
+  fn work(&self);
+}
+
+/// Whether a worker was asked to stop, along with the condvar that wakes it up.
+type Stopped = Arc<(Mutex<bool>, Condvar)>;
+
+type Handle = (Stopped, Arc<dyn Worker>, JoinHandle<()>);
+
+/// The workers of a logger, which run every second until they are stopped.
+#[derive(Debug, Clone, Default)]
+pub struct Workers {
+  handles: Arc<Mutex<Vec<Handle>>>,
+}
+
+impl Workers {
+  pub fn spawn<W: Worker>(&self, worker: W) {
+    let stopped = Stopped::default();
+    let worker_stopped = stopped.clone();
+    let worker = Arc::new(worker);
+    let task_worker = worker.clone();
+
+    let handle = async_std::task::spawn_blocking(move || loop {
+      let is_stopped = {
+        let (lock, condvar) = &*worker_stopped;
+        let guard = lock.lock().unwrap();
+
+        *condvar
+          .wait_timeout_while(guard, INTERVAL, |s| !*s)
+          .unwrap()
+          .0
+      };
+
+      // The last round sends what was logged before stopping
+      task_worker.work();
+
+      if is_stopped {
+        break;
+      }
+    });
+
+    self.handles.lock().unwrap().push((stopped, worker, handle));
+  }
+
+  /// Runs a round of every worker right away, in the order they were spawned.
+  pub fn flush(&self) {
+    let workers = {
+      let handles = self.handles.lock().unwrap();
+
+      handles.iter().map(|h| h.1.clone()).collect::<Vec<_>>()
+    };
+
+    for worker in workers {
+      worker.work();
+    }
+  }
+
+  /// Wakes up the workers for a last round and waits for them to finish it.
+  ///
+  /// They are stopped one after the other in the order they were spawned, so what a worker
+  /// sends in its last round reaches the last round of the ones spawned after it.
+  pub fn stop(&self) {
+    let handles = std::mem::take(&mut *self.handles.lock().unwrap());
+
+    for (stopped, _, handle) in handles {
+      {
+        let (lock, condvar) = &*stopped;
+        *lock.lock().unwrap() = true;
+        condvar.notify_all();
+      }
+
+      async_std::task::block_on(handle);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::atomic::{AtomicUsize, Ordering};
+
+  use super::*;
+
+  #[derive(Debug, Default)]
+  struct Counter(Arc<AtomicUsize>);
+
+  impl Worker for Counter {
+    fn work(&self) {
+      self.0.fetch_add(1, Ordering::SeqCst);
+    }
+  }
+
+  #[test]
+  fn stop_runs_a_last_round_right_away() {
+    let workers = Workers::default();
+    let rounds = Arc::new(AtomicUsize::new(0));
+    workers.spawn(Counter(rounds.clone()));
+
+    workers.stop();
+
+    assert_eq!(rounds.load(Ordering::SeqCst), 1);
+  }
+
+  #[test]
+  fn flush_runs_a_round_right_away() {
+    let workers = Workers::default();
+    let rounds = Arc::new(AtomicUsize::new(0));
+    workers.spawn(Counter(rounds.clone()));
+
+    workers.flush();
+    assert_eq!(rounds.load(Ordering::SeqCst), 1);
+
+    workers.stop();
+    assert_eq!(rounds.load(Ordering::SeqCst), 2);
+  }
+
+  #[test]
+  fn stop_runs_the_last_rounds_in_order() {
+    #[derive(Debug)]
+    struct Recorder(usize, Arc<Mutex<Vec<usize>>>);
+
+    impl Worker for Recorder {
+      fn work(&self) {
+        self.1.lock().unwrap().push(self.0);
+      }
+    }
+
+    let workers = Workers::default();
+    let rounds = Arc::new(Mutex::new(Vec::new()));
+
+    for i in 0..3 {
+      workers.spawn(Recorder(i, rounds.clone()));
+    }
+
+    workers.stop();
+
+    assert_eq!(*rounds.lock().unwrap(), [0, 1, 2]);
+  }
+
+  #[test]
+  fn workers_dont_run_after_stopping() {
+    let workers = Workers::default();
+    let rounds = Arc::new(AtomicUsize::new(0));
+    workers.spawn(Counter(rounds.clone()));
+    workers.spawn(Counter(rounds.clone()));
+
+    workers.stop();
+    let stopped_rounds = rounds.load(Ordering::SeqCst);
+    std::thread::sleep(INTERVAL + Duration::from_millis(200));
+
+    assert_eq!(stopped_rounds, 2);
+    assert_eq!(rounds.load(Ordering::SeqCst), stopped_rounds);
+  }
+
+  #[test]
+  fn workers_run_every_interval() {
+    let workers = Workers::default();
+    let rounds = Arc::new(AtomicUsize::new(0));
+    workers.spawn(Counter(rounds.clone()));
+
+    std::thread::sleep(INTERVAL + Duration::from_millis(200));
+    workers.stop();
+
+    assert_eq!(rounds.load(Ordering::SeqCst), 2);
+  }
+}
@@ -2,3 +2,15 @@
 // Check the README file in the project root for more information.
 
 pub use commonlib_macros::as_variant;
+
+/// Whether both stdout and stderr support 256 colors, which is when the colored output is used.
+#[cfg(feature = "colorful-logs")]
+pub(crate) fn supports_color() -> bool {
+  let supports = |stream| {
+    supports_color::on_cached(stream)
+      .map(|a| a.has_256)
+      .unwrap_or(false)
+  };
+
+  supports(supports_color::Stream::Stdout) && supports(supports_color::Stream::Stderr)
+}
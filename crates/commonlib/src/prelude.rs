@@ -1,5 +1,6 @@
 // Copyright (c) Toolbi Software. All rights reserved.
 // Check the README file in the project root for more information.
 
+pub use crate::error::{ErrorExt, ResultExt};
 pub use crate::num::Round;
 pub use crate::str::{PadEq, PadLen, Trunc};
@@ -19,7 +19,7 @@ fn pad_eq_(string: &str, length: usize) -> String {
   }
 }
 
-impl<'a> PadEq for Vec<&'a str> {
+impl PadEq for Vec<&str> {
   type Output = Vec<String>;
 
   fn pad_eq(&self) -> Self::Output {
@@ -47,14 +47,14 @@ impl PadEq for Vec<String> {
   }
 }
 
-impl<'a, K> PadEq for HashMap<K, &'a str>
+impl<K> PadEq for HashMap<K, &str>
 where
   K: Hash + Eq + Clone,
 {
   type Output = HashMap<K, String>;
 
   fn pad_eq(&self) -> Self::Output {
-    let len = self.iter().map(|(_, v)| v.len()).max().unwrap_or(0);
+    let len = self.values().map(|v| v.len()).max().unwrap_or(0);
     let mut result = HashMap::new();
     for (key, value) in self {
       result.insert(key.to_owned(), pad_eq_(value, len));
@@ -71,7 +71,7 @@ where
   type Output = HashMap<K, String>;
 
   fn pad_eq(&self) -> Self::Output {
-    let len = self.iter().map(|(_, v)| v.len()).max().unwrap_or(0);
+    let len = self.values().map(|v| v.len()).max().unwrap_or(0);
     let mut result = HashMap::new();
     for (key, value) in self {
       result.insert(key.to_owned(), pad_eq_(value, len));
@@ -36,7 +36,7 @@ impl PadLen for String {
   }
 }
 
-impl<'a> PadLen for Vec<&'a str> {
+impl PadLen for Vec<&str> {
   type Output = Vec<String>;
 
   fn pad_len(&self, length: usize) -> Self::Output {
@@ -62,7 +62,7 @@ impl PadLen for Vec<String> {
   }
 }
 
-impl<'a, K> PadLen for HashMap<K, &'a str>
+impl<K> PadLen for HashMap<K, &str>
 where
   K: Hash + Eq + Clone,
 {
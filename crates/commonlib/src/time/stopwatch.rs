@@ -24,6 +24,12 @@ impl Stopwatch {
   }
 }
 
+impl Default for Stopwatch {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
 impl Stopwatch<Stopped> {
   pub fn start(self) -> Stopwatch<Running> {
     Stopwatch {
@@ -88,14 +94,11 @@ impl Stopwatch<Running> {
     time.usf(decimals)
   }
 
-  pub fn stop(self) {
-    drop(self)
-  }
+  pub fn stop(self) {}
 
   fn elapsed_to_time(&self) -> Time {
     let value = self.start_time.unwrap().elapsed();
-    let time = Time::from(value);
 
-    time
+    Time::from(value)
   }
 }
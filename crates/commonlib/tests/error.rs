@@ -0,0 +1,217 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use std::error::Error as _;
+
+use commonlib::{error::ChainFrame, Error, ErrorExt};
+
+#[derive(Debug)]
+struct ParseError {
+  source: std::io::Error,
+}
+
+impl std::fmt::Display for ParseError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "couldn't parse the file")
+  }
+}
+
+impl std::error::Error for ParseError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    Some(&self.source)
+  }
+}
+
+fn parse_error() -> ParseError {
+  ParseError {
+    source: std::io::Error::other("unexpected end of file"),
+  }
+}
+
+fn get_source_messages(error: &Error) -> Vec<String> {
+  let mut result = Vec::new();
+  let mut current: Option<&(dyn std::error::Error + 'static)> = Some(error);
+
+  while let Some(error) = current {
+    result.push(match error.downcast_ref::<Error>() {
+      Some(error) => error.message().to_string(),
+      None => error.to_string(),
+    });
+
+    current = error.source();
+  }
+
+  result
+}
+
+#[test]
+fn from_error_stands_for_the_error() {
+  let error = Error::from_error(parse_error());
+
+  assert_eq!(error.message(), "couldn't parse the file");
+  assert!(error.location().is_none());
+  assert_eq!(
+    error.to_string(),
+    "error: couldn't parse the file\n╰─▶ error: unexpected end of file\n"
+  );
+}
+
+#[test]
+fn from_error_source_is_the_source_of_the_error() {
+  let error = Error::from_error(parse_error());
+
+  let source = error.source().unwrap();
+
+  assert_eq!(source.to_string(), "unexpected end of file");
+  assert!(source.downcast_ref::<std::io::Error>().is_some());
+  assert!(source.source().is_none());
+}
+
+#[test]
+fn from_error_without_source_ends_the_chain() {
+  let error = Error::from_error(std::io::Error::other("disk full"));
+
+  assert!(error.source().is_none());
+  assert_eq!(error.to_string(), "error: disk full\n");
+}
+
+#[test]
+fn from_error_keeps_an_error() {
+  let error = Error::from_error(Error::from_error(parse_error()).set_category("PARSE"));
+
+  assert_eq!(error.category(), Some("PARSE"));
+  assert!(error.downcast_ref::<ParseError>().is_some());
+}
+
+#[test]
+fn downcast_ref_returns_the_converted_error() {
+  let error = Error::from_error(parse_error());
+
+  let parse_error = error.downcast_ref::<ParseError>().unwrap();
+
+  assert_eq!(parse_error.source.to_string(), "unexpected end of file");
+  assert!(error.downcast_ref::<std::io::Error>().is_none());
+}
+
+#[test]
+fn downcast_ref_only_looks_at_the_layer() {
+  let error = Error::new("not converted");
+  let context = parse_error().context("couldn't load the config");
+
+  assert!(error.downcast_ref::<ParseError>().is_none());
+  assert!(context.downcast_ref::<ParseError>().is_none());
+  assert!(context
+    .chain()
+    .find_map(|f| f.downcast_ref::<ParseError>())
+    .is_some());
+}
+
+#[test]
+fn downcast_takes_the_converted_error() {
+  let error = Error::from_error(parse_error()).set_category("PARSE");
+
+  let error = error.downcast::<std::io::Error>().unwrap_err();
+  assert_eq!(error.category(), Some("PARSE"));
+
+  let parse_error = error.downcast::<ParseError>().unwrap();
+  assert_eq!(parse_error.to_string(), "couldn't parse the file");
+}
+
+#[test]
+fn chain_matches_the_sources() {
+  let error = Error::from_error(parse_error())
+    .context("couldn't load the config")
+    .context("couldn't start");
+
+  let chain_messages = error
+    .chain()
+    .map(|f| f.message().to_string())
+    .collect::<Vec<_>>();
+
+  assert_eq!(chain_messages, get_source_messages(&error));
+  assert_eq!(
+    chain_messages,
+    [
+      "couldn't start",
+      "couldn't load the config",
+      "couldn't parse the file",
+      "unexpected end of file"
+    ]
+  );
+}
+
+#[test]
+fn display_matches_the_chain() {
+  let error = Error::from_error(parse_error()).context("couldn't load the config");
+
+  let headings = error
+    .to_string()
+    .lines()
+    .filter_map(|l| l.split_once("error: ").map(|(_, m)| m.to_string()))
+    .collect::<Vec<_>>();
+
+  assert_eq!(headings, get_source_messages(&error));
+}
+
+#[test]
+fn context_keeps_the_foreign_error_as_a_layer() {
+  let error = parse_error().context("couldn't load the config");
+
+  let source = error.source().unwrap();
+
+  assert!(source.downcast_ref::<ParseError>().is_some());
+  assert_eq!(
+    get_source_messages(&error),
+    [
+      "couldn't load the config",
+      "couldn't parse the file",
+      "unexpected end of file"
+    ]
+  );
+}
+
+#[test]
+fn location_points_to_the_caller() {
+  let line = line!() + 1;
+  let error = Error::new("couldn't start");
+
+  let location = error.location().unwrap();
+  let (line_number, column): (u32, u32) = (location.line(), location.column());
+
+  assert_eq!(location.file(), file!());
+  assert_eq!(line_number, line);
+  assert_eq!(column, 15);
+}
+
+#[test]
+fn context_is_located_at_the_caller() {
+  let error = parse_error().context("couldn't load the config");
+
+  assert_eq!(error.location().unwrap().line(), line!() - 2);
+  assert!(error.to_string().contains(&format!(
+    "at {}:{}:",
+    file!(),
+    error.location().unwrap().line()
+  )));
+}
+
+#[test]
+fn chain_frames_tell_the_layers_apart() {
+  let error = Error::from_error(parse_error())
+    .set_category("PARSE")
+    .context("couldn't load the config");
+
+  let frames = error.chain().collect::<Vec<_>>();
+
+  assert_eq!(frames.len(), 3);
+  assert!(matches!(frames[0], ChainFrame::Error(_)));
+  assert!(frames[0].location().is_some());
+  assert_eq!(frames[1].category(), Some("PARSE"));
+  assert!(frames[1].location().is_none());
+  assert!(matches!(frames[2], ChainFrame::Foreign(_)));
+  assert_eq!(frames[2].message(), "unexpected end of file");
+  assert!(frames[2]
+    .as_std()
+    .downcast_ref::<std::io::Error>()
+    .is_some());
+}
@@ -0,0 +1,45 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use commonlib::{Error, ErrorExt};
+
+#[test]
+fn set_backtrace_renders_the_frames_under_the_location() {
+  let error = Error::new("couldn't start").set_backtrace(true);
+
+  let display = error.to_string();
+  let lines = display.lines().collect::<Vec<_>>();
+
+  assert!(error.backtrace().is_some());
+  assert!(lines[1].starts_with("╰╴at ") && lines[1].contains("tests/error_backtrace.rs:"));
+  assert!(lines[2..].iter().any(|l| l
+    .trim_start()
+    .starts_with("↳ error_backtrace::set_backtrace_renders_the_frames_under_the_location at")));
+  assert!(!display.contains("commonlib::error::"));
+}
+
+#[test]
+fn set_backtrace_can_drop_it() {
+  let error = Error::new("couldn't start")
+    .set_backtrace(true)
+    .set_backtrace(false);
+
+  assert!(error.backtrace().is_none());
+  assert!(!error.to_string().contains('↳'));
+}
+
+#[test]
+fn context_doesnt_repeat_the_frames() {
+  let error = Error::new("couldn't parse the file")
+    .set_backtrace(true)
+    .context("couldn't load the config");
+
+  assert!(error.backtrace().is_none());
+  assert_eq!(
+    error
+      .to_string()
+      .matches("↳ error_backtrace::context_doesnt_repeat_the_frames at")
+      .count(),
+    1
+  );
+}
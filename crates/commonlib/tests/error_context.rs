@@ -0,0 +1,124 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use std::error::Error as _;
+
+use commonlib::{Error, ResultExt};
+
+fn read_config() -> Result<String, std::io::Error> {
+  Err(std::io::Error::other("file not found"))
+}
+
+fn get_chain_messages(error: &Error) -> Vec<String> {
+  error.chain().map(|f| f.message().to_string()).collect()
+}
+
+#[test]
+fn result_context_wraps_the_error() {
+  let line = line!() + 1;
+  let error = read_config().context("couldn't load the config");
+  let error = error.unwrap_err();
+
+  assert_eq!(error.location().unwrap().file(), file!());
+  assert_eq!(error.location().unwrap().line(), line);
+  assert_eq!(
+    get_chain_messages(&error),
+    ["couldn't load the config", "file not found"]
+  );
+  assert!(error
+    .source()
+    .unwrap()
+    .downcast_ref::<std::io::Error>()
+    .is_some());
+}
+
+#[test]
+fn result_with_context_is_only_built_on_errors() {
+  let mut calls = 0;
+
+  let value = Ok::<_, std::io::Error>(1).with_context(|| {
+    calls += 1;
+    "never built"
+  });
+  assert_eq!(value.unwrap(), 1);
+  assert_eq!(calls, 0);
+
+  let error = read_config()
+    .with_context(|| format!("couldn't load '{}'", "app.toml"))
+    .unwrap_err();
+  assert_eq!(error.message(), "couldn't load 'app.toml'");
+}
+
+#[test]
+fn result_category_converts_the_error() {
+  let error = read_config().category("CONFIG").unwrap_err();
+
+  assert_eq!(error.message(), "file not found");
+  assert_eq!(error.category(), Some("CONFIG"));
+  assert!(error.location().is_some());
+  assert!(error.downcast_ref::<std::io::Error>().is_some());
+}
+
+#[test]
+fn result_category_keeps_the_location_of_an_error() {
+  let error = Error::new("couldn't parse the file");
+  let line = error.location().unwrap().line();
+
+  let error = Err::<(), _>(error).category("PARSE").unwrap_err();
+
+  assert_eq!(error.category(), Some("PARSE"));
+  assert_eq!(error.location().unwrap().line(), line);
+}
+
+#[test]
+fn contexts_build_a_chain() {
+  let error = read_config()
+    .context("couldn't load the config")
+    .context("couldn't start")
+    .category("APP")
+    .unwrap_err();
+
+  assert_eq!(error.category(), Some("APP"));
+  assert_eq!(
+    get_chain_messages(&error),
+    [
+      "couldn't start",
+      "couldn't load the config",
+      "file not found"
+    ]
+  );
+}
+
+#[test]
+fn option_context_reports_the_missing_value() {
+  let error = None::<u16>.context("the port is missing").unwrap_err();
+  assert_eq!(error.message(), "the port is missing");
+  assert!(error.location().is_some());
+  assert!(error.source().is_none());
+
+  let error = None::<u16>
+    .with_context(|| format!("the {} is missing", "port"))
+    .unwrap_err();
+  assert_eq!(error.message(), "the port is missing");
+
+  assert_eq!(Some(80).context("the port is missing").unwrap(), 80);
+}
+
+#[test]
+fn option_category_has_a_default_message() {
+  let error = None::<u16>.category("CONFIG").unwrap_err();
+
+  assert_eq!(error.message(), "the value is missing");
+  assert_eq!(error.category(), Some("CONFIG"));
+}
+
+#[test]
+fn aliases_match_the_methods() {
+  let error = read_config()
+    .context_err("couldn't load the config")
+    .with_category("CONFIG")
+    .unwrap_err();
+
+  assert_eq!(error.category(), Some("CONFIG"));
+  assert_eq!(error.message(), "couldn't load the config");
+}
@@ -0,0 +1,94 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+#![cfg(any(feature = "anyhow", feature = "eyre"))]
+
+use commonlib::{Error, ResultExt};
+
+fn get_chain_messages(error: &Error) -> Vec<String> {
+  error.chain().map(|f| f.message().to_string()).collect()
+}
+
+fn get_error() -> Error {
+  Error::new("couldn't parse the file").set_category("PARSE")
+}
+
+#[cfg(feature = "anyhow")]
+#[test]
+fn anyhow_round_trip_keeps_the_error() {
+  let error = get_error();
+  let line = error.location().unwrap().line();
+
+  let error = Error::from(anyhow::Error::from(error));
+
+  assert_eq!(error.message(), "couldn't parse the file");
+  assert_eq!(error.category(), Some("PARSE"));
+  assert_eq!(error.location().unwrap().line(), line);
+}
+
+#[cfg(feature = "anyhow")]
+#[test]
+fn anyhow_context_is_kept_as_a_layer() {
+  let report = anyhow::Error::from(get_error()).context("couldn't load the config");
+
+  let error = Error::from(report);
+
+  assert!(error.location().is_none());
+  assert_eq!(
+    get_chain_messages(&error),
+    ["couldn't load the config", "couldn't parse the file"]
+  );
+  assert_eq!(error.chain().nth(1).unwrap().category(), Some("PARSE"));
+}
+
+#[cfg(feature = "anyhow")]
+#[test]
+fn anyhow_result_takes_context() {
+  let result: Result<(), anyhow::Error> = Err(anyhow::anyhow!("disk full"));
+
+  let error = result.context("couldn't save").unwrap_err();
+
+  assert!(error.location().is_some());
+  assert_eq!(get_chain_messages(&error), ["couldn't save", "disk full"]);
+}
+
+#[cfg(feature = "eyre")]
+#[test]
+fn eyre_round_trip_keeps_the_error() {
+  let error = get_error();
+  let line = error.location().unwrap().line();
+
+  let error = Error::from(eyre::Report::from(error));
+
+  assert_eq!(error.message(), "couldn't parse the file");
+  assert_eq!(error.category(), Some("PARSE"));
+  assert_eq!(error.location().unwrap().line(), line);
+}
+
+#[cfg(feature = "eyre")]
+#[test]
+fn eyre_context_is_kept_as_a_layer() {
+  use eyre::WrapErr;
+
+  let result: Result<(), Error> = Err(get_error());
+  let report = result.wrap_err("couldn't load the config").unwrap_err();
+
+  let error = Error::from(report);
+
+  assert_eq!(
+    get_chain_messages(&error),
+    ["couldn't load the config", "couldn't parse the file"]
+  );
+}
+
+#[cfg(feature = "eyre")]
+#[test]
+fn eyre_result_takes_a_category() {
+  let result: Result<(), eyre::Report> = Err(eyre::eyre!("disk full"));
+
+  let error = result.category("IO").unwrap_err();
+
+  assert_eq!(error.message(), "disk full");
+  assert_eq!(error.category(), Some("IO"));
+  assert!(error.location().is_some());
+}
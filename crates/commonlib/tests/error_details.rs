@@ -0,0 +1,113 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use commonlib::{Error, ErrorExt};
+
+fn get_lines(error: &Error) -> Vec<String> {
+  error.to_string().lines().map(String::from).collect()
+}
+
+fn get_error() -> Error {
+  Error::from_error(std::io::Error::other("file not found"))
+}
+
+#[test]
+fn accessors_keep_the_order() {
+  let error = get_error()
+    .attach("path", "config.toml")
+    .attach("retries", 3)
+    .attach("path", "config.yaml")
+    .note("it's read at startup")
+    .help("create it with `init`")
+    .help("or pass --config");
+
+  assert_eq!(
+    error.attachments().collect::<Vec<_>>(),
+    [
+      ("path", "config.toml"),
+      ("retries", "3"),
+      ("path", "config.yaml")
+    ]
+  );
+  assert_eq!(error.attachment("path"), Some("config.toml"));
+  assert_eq!(error.attachment("user"), None);
+  assert_eq!(error.notes(), ["it's read at startup"]);
+  assert_eq!(error.helps(), ["create it with `init`", "or pass --config"]);
+}
+
+#[test]
+fn details_are_branches_of_the_layer() {
+  let error = get_error()
+    .attach("path", "config.toml")
+    .note("it's read at startup")
+    .help("create it with `init`");
+
+  assert_eq!(
+    get_lines(&error),
+    [
+      "error: file not found",
+      "├╴path: config.toml",
+      "├╴note: it's read at startup",
+      "╰╴help: create it with `init`"
+    ]
+  );
+}
+
+#[test]
+fn multi_line_values_keep_the_tree() {
+  let error = get_error()
+    .attach("query", "SELECT *\nFROM users")
+    .note("the table is locked\nby a migration");
+
+  assert_eq!(
+    get_lines(&error),
+    [
+      "error: file not found",
+      "├╴query: SELECT *",
+      "│ FROM users",
+      "╰╴note: the table is locked",
+      "  by a migration"
+    ]
+  );
+}
+
+#[test]
+fn multi_line_values_keep_the_nested_tree() {
+  let error = get_error()
+    .attach("query", "SELECT *\nFROM users")
+    .context("couldn't load the users")
+    .set_backtrace(false);
+  let location = error.location().unwrap();
+
+  assert_eq!(
+    get_lines(&error),
+    [
+      "error: couldn't load the users".into(),
+      format!(
+        "├╴at {}:{}:{}",
+        location.file(),
+        location.line(),
+        location.column()
+      ),
+      "│".into(),
+      "╰─▶ error: file not found".into(),
+      "    ╰╴query: SELECT *".into(),
+      "      FROM users".to_string()
+    ]
+  );
+}
+
+#[test]
+fn multi_line_messages_keep_the_tree() {
+  let error = Error::from_error(std::io::Error::other("file not found\nin ./config"))
+    .note("it's read at startup");
+
+  assert_eq!(
+    get_lines(&error),
+    [
+      "error: file not found",
+      "│ in ./config",
+      "╰╴note: it's read at startup"
+    ]
+  );
+}
@@ -0,0 +1,56 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use commonlib::{Error, ErrorExt};
+
+fn get_error() -> Error {
+  std::io::Error::other("file not found")
+    .context("couldn't load the config")
+    .set_category("CONFIG")
+}
+
+#[cfg(feature = "colorful-logs")]
+fn strip_colors(string: &str) -> String {
+  let mut result = String::new();
+  let mut chars = string.chars();
+
+  while let Some(char) = chars.next() {
+    match char {
+      '\x1b' => {
+        chars.by_ref().find(|c| *c == 'm');
+      }
+      _ => result.push(char),
+    }
+  }
+
+  result
+}
+
+#[test]
+fn display_is_plain() {
+  let error = get_error();
+
+  assert!(!error.to_string().contains('\x1b'));
+  assert!(error
+    .to_string()
+    .starts_with("error (CONFIG): couldn't load the config\n"));
+}
+
+#[cfg(feature = "colorful-logs")]
+#[test]
+fn display_colored_paints_the_same_tree() {
+  let error = get_error();
+
+  let colored = error.display_colored().to_string();
+
+  assert!(colored.contains('\x1b'));
+  assert_eq!(strip_colors(&colored), error.to_string());
+}
+
+#[cfg(not(feature = "colorful-logs"))]
+#[test]
+fn alternate_display_is_plain_without_colors() {
+  let error = get_error();
+
+  assert_eq!(format!("{:#}", error), error.to_string());
+}
@@ -0,0 +1,17 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+#![cfg(feature = "colorful-logs")]
+
+// The color support is detected once per process, so this is the only test here
+use commonlib::Error;
+
+#[test]
+fn alternate_display_is_colored_when_the_terminal_supports_it() {
+  std::env::set_var("FORCE_COLOR", "3");
+
+  let error = Error::new("couldn't load the config").set_category("CONFIG");
+
+  assert_eq!(format!("{:#}", error), error.display_colored().to_string());
+  assert_ne!(format!("{:#}", error), error.to_string());
+}
@@ -0,0 +1,74 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use commonlib::{Error, ErrorExt, ResultExt};
+
+#[derive(Debug, PartialEq)]
+enum ConfigError {
+  Missing,
+}
+
+#[derive(Debug, PartialEq)]
+enum AppError {
+  Startup,
+}
+
+fn get_missing_port() -> Error {
+  Error::new("the port is missing").set_kind(ConfigError::Missing)
+}
+
+#[test]
+fn kind_is_found_through_the_chain() {
+  let error = Err::<(), _>(get_missing_port())
+    .context("couldn't load the config")
+    .context("couldn't start")
+    .unwrap_err();
+
+  assert!(error.is::<ConfigError>());
+  assert_eq!(error.kind::<ConfigError>(), Some(&ConfigError::Missing));
+  assert!(!error.is::<AppError>());
+  assert!(error
+    .chain()
+    .next()
+    .unwrap()
+    .kind::<ConfigError>()
+    .is_none());
+}
+
+#[test]
+fn kind_is_the_outermost_one() {
+  #[derive(Debug, PartialEq)]
+  struct Retries(u8);
+
+  let error = Error::new("timed out")
+    .set_kind(Retries(1))
+    .context("couldn't connect")
+    .set_kind(Retries(3));
+
+  assert_eq!(error.kind::<Retries>(), Some(&Retries(3)));
+}
+
+#[test]
+fn kind_is_kept_by_other_layers() {
+  let error = Err::<(), _>(get_missing_port())
+    .context("couldn't start")
+    .map_err(|e| e.set_kind(AppError::Startup))
+    .unwrap_err();
+
+  assert_eq!(error.kind::<AppError>(), Some(&AppError::Startup));
+  assert_eq!(error.kind::<ConfigError>(), Some(&ConfigError::Missing));
+}
+
+#[test]
+fn code_is_shown_in_the_heading() {
+  let error = std::io::Error::other("file not found")
+    .context("couldn't load the config")
+    .set_code("E_CONFIG_MISSING")
+    .set_category("CONFIG");
+
+  let display = error.to_string();
+
+  assert_eq!(error.code(), Some("E_CONFIG_MISSING"));
+  assert!(display.starts_with("error[E_CONFIG_MISSING] (CONFIG): couldn't load the config\n"));
+  assert!(display.contains("╰─▶ error: file not found\n"));
+}
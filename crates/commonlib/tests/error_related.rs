@@ -0,0 +1,111 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+use commonlib::{Error, ErrorExt};
+
+fn get_problem(message: &str) -> Error {
+  Error::from_error(std::io::Error::other(message.to_string()))
+}
+
+fn get_lines(error: &Error) -> Vec<String> {
+  error.to_string().lines().map(String::from).collect()
+}
+
+#[test]
+fn from_many_keeps_the_related_errors() {
+  let error = Error::from_many(
+    "the config is invalid",
+    [
+      get_problem("the port is missing"),
+      get_problem("the host is empty"),
+    ],
+  );
+
+  let messages = error
+    .related()
+    .iter()
+    .map(|e| e.message())
+    .collect::<Vec<_>>();
+
+  assert_eq!(messages, ["the port is missing", "the host is empty"]);
+  assert!(error.chain().nth(1).is_none());
+}
+
+#[test]
+fn push_related_adds_the_errors_in_order() {
+  let error = get_problem("the config is invalid")
+    .push_related(get_problem("the port is missing"))
+    .push_related(std::io::Error::other("the host is empty"));
+
+  assert_eq!(error.related().len(), 2);
+  assert_eq!(error.related()[1].message(), "the host is empty");
+  assert!(error.related()[1].location().is_none());
+}
+
+#[test]
+fn related_errors_are_sibling_branches() {
+  let error = get_problem("the config is invalid")
+    .push_related(get_problem("the port is missing").note("it's required"))
+    .push_related(get_problem("the host is empty"));
+
+  assert_eq!(
+    get_lines(&error),
+    [
+      "error: the config is invalid",
+      "├─▶ error: the port is missing",
+      "│   ╰╴note: it's required",
+      "│",
+      "╰─▶ error: the host is empty"
+    ]
+  );
+}
+
+#[test]
+fn related_errors_go_before_the_source() {
+  let error = Error::from_error(std::io::Error::other("file not found"))
+    .context("couldn't load the config")
+    .set_backtrace(false)
+    .push_related(get_problem("the cache is stale"));
+  let location = error.location().unwrap();
+
+  assert_eq!(
+    get_lines(&error),
+    [
+      "error: couldn't load the config".into(),
+      format!(
+        "├╴at {}:{}:{}",
+        location.file(),
+        location.line(),
+        location.column()
+      ),
+      "│".into(),
+      "├─▶ error: the cache is stale".into(),
+      "│".into(),
+      "╰─▶ error: file not found".to_string()
+    ]
+  );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn related_errors_survive_the_serde_round_trip() {
+  let error = Error::from_many(
+    "the config is invalid",
+    [
+      get_problem("the port is missing").set_category("CONFIG"),
+      get_problem("the host is empty").push_related(get_problem("the DNS is down")),
+    ],
+  )
+  .set_backtrace(false);
+
+  let json = serde_json::to_string(&error).unwrap();
+  let error_back = serde_json::from_str::<Error>(&json).unwrap();
+
+  assert_eq!(error_back.related().len(), 2);
+  assert_eq!(error_back.related()[0].category(), Some("CONFIG"));
+  assert_eq!(
+    error_back.related()[1].related()[0].message(),
+    "the DNS is down"
+  );
+  assert_eq!(error_back.to_string(), error.to_string());
+}
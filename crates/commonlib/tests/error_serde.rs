@@ -0,0 +1,77 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+#![cfg(feature = "serde")]
+
+use commonlib::{Error, ErrorExt};
+
+fn get_chain_messages(error: &Error) -> Vec<String> {
+  error.chain().map(|f| f.message().to_string()).collect()
+}
+
+#[test]
+fn round_trip_keeps_the_chain() {
+  let error = std::io::Error::other("file not found")
+    .context("couldn't load the config")
+    .set_category("CONFIG")
+    .set_code("E_CONFIG_MISSING")
+    .attach("path", "config.toml")
+    .note("it's read at startup")
+    .help("create it with `init`")
+    // The backtraces aren't serialized
+    .set_backtrace(false);
+
+  let json = serde_json::to_string(&error).unwrap();
+  let error_back = serde_json::from_str::<Error>(&json).unwrap();
+
+  assert_eq!(get_chain_messages(&error_back), get_chain_messages(&error));
+  assert_eq!(error_back.category(), Some("CONFIG"));
+  assert_eq!(error_back.code(), Some("E_CONFIG_MISSING"));
+  assert_eq!(error_back.attachment("path"), Some("config.toml"));
+  assert_eq!(error_back.notes(), ["it's read at startup"]);
+  assert_eq!(error_back.helps(), ["create it with `init`"]);
+  assert_eq!(
+    error_back.location().unwrap().line(),
+    error.location().unwrap().line()
+  );
+  assert_eq!(error_back.to_string(), error.to_string());
+  assert_eq!(serde_json::to_string(&error_back).unwrap(), json);
+}
+
+#[test]
+fn foreign_errors_only_keep_their_message() {
+  let error = std::io::Error::other("file not found").context("couldn't load the config");
+
+  let json = serde_json::to_value(&error).unwrap();
+
+  assert_eq!(json["source"]["message"], "file not found");
+  assert!(json["source"]["location"].is_null());
+  assert!(json["source"]["source"].is_null());
+}
+
+#[test]
+fn older_errors_are_read() {
+  let json = r#"{
+    "message": "couldn't start",
+    "category": "APP",
+    "location": { "file": "src/main.rs", "line": 8, "column": 3 },
+    "source": {
+      "message": "file not found",
+      "category": null,
+      "location": null,
+      "source": null
+    }
+  }"#;
+
+  let error = serde_json::from_str::<Error>(json).unwrap();
+
+  assert_eq!(
+    get_chain_messages(&error),
+    ["couldn't start", "file not found"]
+  );
+  assert_eq!(error.category(), Some("APP"));
+  assert_eq!(error.location().unwrap().file(), "src/main.rs");
+  assert_eq!(error.code(), None);
+  assert!(error.attachments().next().is_none());
+  assert!(error.related().is_empty());
+}
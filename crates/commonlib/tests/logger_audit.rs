@@ -0,0 +1,217 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+#![cfg(feature = "logger-audit")]
+
+mod support;
+
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+use commonlib::logger::{
+  audit::{self, Error},
+  LogLevel, Logger,
+};
+use support::TempDir;
+
+const KEY: &[u8] = b"audit key";
+
+fn get_logger(dir_path: &Path, key: Option<&[u8]>) -> Logger {
+  Logger::new()
+    .set_level(LogLevel::Info)
+    .setup_file_logger(true, Some(dir_path.to_string_lossy().to_string()))
+    .setup_file_audit(true, key)
+}
+
+fn write_logs(logger: Logger, messages: &[&str]) {
+  let logger = logger.init_scoped().unwrap();
+
+  for message in messages {
+    tracing::info!("{}", message);
+  }
+
+  drop(logger);
+}
+
+fn get_file_path(dir_path: &Path, suffix: &str) -> PathBuf {
+  fs::read_dir(dir_path)
+    .unwrap()
+    .map(|e| e.unwrap().path())
+    .find(|p| p.to_string_lossy().ends_with(suffix))
+    .unwrap()
+}
+
+fn edit_audit_file(dir_path: &Path, edit: impl FnOnce(&mut Vec<String>)) {
+  let path = get_file_path(dir_path, "-audit.csv");
+  let mut lines = fs::read_to_string(&path)
+    .unwrap()
+    .lines()
+    .map(String::from)
+    .collect();
+
+  edit(&mut lines);
+  fs::write(&path, lines.join("\n") + "\n").unwrap();
+}
+
+#[test]
+fn append_chains_every_log() {
+  let dir_path = TempDir::new("audit-append");
+
+  write_logs(
+    get_logger(&dir_path, Some(KEY)),
+    &["first", "second", "third"],
+  );
+
+  assert_eq!(audit::verify(&dir_path, Some(KEY)).unwrap(), 3);
+}
+
+#[test]
+fn resume_continues_the_chain() {
+  let dir_path = TempDir::new("audit-resume");
+
+  write_logs(get_logger(&dir_path, Some(KEY)), &["first", "second"]);
+  write_logs(get_logger(&dir_path, Some(KEY)), &["after the restart"]);
+
+  assert_eq!(audit::verify(&dir_path, Some(KEY)).unwrap(), 3);
+}
+
+#[test]
+fn resume_cuts_a_partial_last_line() {
+  let dir_path = TempDir::new("audit-partial");
+
+  write_logs(get_logger(&dir_path, Some(KEY)), &["first", "second"]);
+
+  // Like a crash in the middle of a write
+  let path = get_file_path(&dir_path, "-audit.csv");
+  let mut content = fs::read_to_string(&path).unwrap();
+  content.push_str("1700000000000,info,,half writ");
+  fs::write(&path, content).unwrap();
+
+  write_logs(get_logger(&dir_path, Some(KEY)), &["after the restart"]);
+
+  assert_eq!(audit::verify(&dir_path, Some(KEY)).unwrap(), 3);
+  assert!(!fs::read_to_string(&path).unwrap().contains("half writ"));
+}
+
+#[test]
+fn verify_detects_a_tampered_line() {
+  let dir_path = TempDir::new("audit-tampered");
+
+  write_logs(get_logger(&dir_path, None), &["paid 10", "paid 20"]);
+  edit_audit_file(&dir_path, |lines| {
+    lines[2] = lines[2].replace("paid 20", "paid 99")
+  });
+
+  assert!(matches!(
+    audit::verify(&dir_path, None),
+    Err(Error::BrokenLink { record: 2, .. })
+  ));
+}
+
+#[test]
+fn verify_detects_a_deleted_line() {
+  let dir_path = TempDir::new("audit-deleted");
+
+  write_logs(get_logger(&dir_path, None), &["first", "second", "third"]);
+  edit_audit_file(&dir_path, |lines| {
+    lines.remove(2);
+  });
+
+  assert!(matches!(
+    audit::verify(&dir_path, None),
+    Err(Error::BrokenLink { record: 2, .. })
+  ));
+}
+
+#[test]
+fn verify_detects_a_truncated_tail() {
+  let dir_path = TempDir::new("audit-truncated");
+
+  write_logs(get_logger(&dir_path, None), &["first", "second", "third"]);
+  edit_audit_file(&dir_path, |lines| {
+    lines.pop();
+  });
+
+  assert!(matches!(
+    audit::verify(&dir_path, None),
+    Err(Error::Truncated { record: 3, .. })
+  ));
+
+  // The logger refuses to continue the chain as well
+  let logger = get_logger(&dir_path, None).init_scoped();
+  assert!(logger.is_err());
+}
+
+#[test]
+fn verify_detects_a_deleted_file() {
+  let dir_path = TempDir::new("audit-deleted-file");
+
+  write_logs(get_logger(&dir_path, Some(KEY)), &["first"]);
+  fs::remove_file(get_file_path(&dir_path, "-audit.csv")).unwrap();
+
+  assert!(matches!(
+    audit::verify(&dir_path, Some(KEY)),
+    Err(Error::Truncated { record: 1, .. })
+  ));
+}
+
+#[test]
+fn verify_needs_the_same_key() {
+  let dir_path = TempDir::new("audit-key");
+
+  write_logs(get_logger(&dir_path, Some(KEY)), &["first", "second"]);
+
+  assert!(matches!(
+    audit::verify(&dir_path, Some(b"another key")),
+    Err(Error::BrokenHead(_))
+  ));
+  assert!(matches!(
+    audit::verify(&dir_path, None),
+    Err(Error::BrokenLink { record: 1, .. })
+  ));
+}
+
+#[test]
+fn keyed_chains_cant_be_rebuilt_without_the_key() {
+  let dir_path = TempDir::new("audit-rebuilt");
+
+  write_logs(get_logger(&dir_path, Some(KEY)), &["paid 10"]);
+
+  // A file with the edited record and its chain recomputed, as anyone could without the key
+  let rebuilt_path = TempDir::new("audit-rebuilt-copy");
+  write_logs(get_logger(&rebuilt_path, None), &["paid 99"]);
+  fs::copy(
+    get_file_path(&rebuilt_path, "-audit.csv"),
+    get_file_path(&dir_path, "-audit.csv"),
+  )
+  .unwrap();
+
+  assert!(matches!(
+    audit::verify(&dir_path, Some(KEY)),
+    Err(Error::BrokenLink { record: 1, .. })
+  ));
+}
+
+#[test]
+fn only_the_audited_categories_are_chained() {
+  let dir_path = TempDir::new("audit-categories");
+
+  let logger = get_logger(&dir_path, Some(KEY))
+    .add_audit_category("payments")
+    .init_scoped()
+    .unwrap();
+
+  tracing::info!(category = "payments", "paid 10");
+  tracing::info!("cache warmed up");
+  tracing::info!(category = "payments", "paid 20");
+  drop(logger);
+
+  let audit_logs = fs::read_to_string(get_file_path(&dir_path, "-audit.csv")).unwrap();
+  let plain_logs = fs::read_to_string(get_file_path(&dir_path, "-log.csv")).unwrap();
+
+  assert_eq!(audit::verify(&dir_path, Some(KEY)).unwrap(), 2);
+  assert!(audit_logs.contains("paid 20") && !audit_logs.contains("cache warmed up"));
+  assert!(plain_logs.contains("cache warmed up") && !plain_logs.contains("paid 10"));
+}
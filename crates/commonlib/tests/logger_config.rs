@@ -0,0 +1,232 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+#![cfg(feature = "logger-config")]
+
+use commonlib::{
+  assert_logged,
+  logger::{ConfigFormat, ConsoleWrap, LogLevel, Logger, LoggerConfig, Sampling},
+};
+
+#[cfg(feature = "logger-redaction")]
+use commonlib::logger::Masking;
+
+fn get_error_messages(error: &commonlib::Error) -> Vec<String> {
+  error.chain().map(|f| f.message().to_string()).collect()
+}
+
+#[test]
+fn parse_reads_every_format() {
+  let toml = LoggerConfig::parse(
+    "level = \"debug\"\n[file_logger]\nenable = true\n",
+    ConfigFormat::Toml,
+  )
+  .unwrap();
+  let json = LoggerConfig::parse(
+    r#"{ "level": "debug", "file_logger": { "enable": true } }"#,
+    ConfigFormat::Json,
+  )
+  .unwrap();
+  let yaml = LoggerConfig::parse(
+    "level: debug\nfile_logger:\n  enable: true\n",
+    ConfigFormat::Yaml,
+  )
+  .unwrap();
+
+  for config in [toml, json, yaml] {
+    assert_eq!(config.level, Some(LogLevel::Debug));
+    assert_eq!(config.file_logger.enable, Some(true));
+  }
+}
+
+#[test]
+fn parse_reads_the_levels_without_case() {
+  let config = LoggerConfig::parse(
+    r#"{ "level": "WARN", "module_filters": { "my_crate::db": "Debug" } }"#,
+    ConfigFormat::Json,
+  )
+  .unwrap();
+
+  assert_eq!(config.level, Some(LogLevel::Warn));
+  assert_eq!(config.module_filters["my_crate::db"], LogLevel::Debug);
+}
+
+#[test]
+fn parse_reads_the_later_settings() {
+  let config = LoggerConfig::parse(
+    r#"
+      [console]
+      wrap = "truncate"
+      width = 80
+
+      [file_logger]
+      audit = true
+      audit_key = "secret"
+      audit_categories = ["payments"]
+
+      [limiter]
+      rate_limit = { count = 10, interval_ms = 1000 }
+      dedup = true
+      category_dedups = { db = false }
+
+      [sampler.level_samplings]
+      debug = { every_nth = 10 }
+      trace = { probability = 0.5 }
+
+      [ring_buffer]
+      enable = true
+      level = "trace"
+      capacity = 50
+
+      [panic_hook]
+      enable = true
+      backtrace = false
+    "#,
+    ConfigFormat::Toml,
+  )
+  .unwrap();
+
+  assert_eq!(config.console.wrap, Some(ConsoleWrap::Truncate));
+  assert_eq!(config.console.width, Some(80));
+  assert_eq!(config.file_logger.audit_key.as_deref(), Some("secret"));
+  assert_eq!(config.file_logger.audit_categories, ["payments"]);
+  assert_eq!(config.limiter.rate_limit.unwrap().interval_ms, 1000);
+  assert_eq!(config.limiter.dedup, Some(true));
+  assert!(!config.limiter.category_dedups["db"]);
+  assert_eq!(
+    config.sampler.level_samplings[&LogLevel::Debug],
+    Sampling::EveryNth(10)
+  );
+  assert_eq!(config.ring_buffer.level, Some(LogLevel::Trace));
+  assert_eq!(config.ring_buffer.capacity, Some(50));
+  assert_eq!(config.panic_hook.backtrace, Some(false));
+}
+
+#[test]
+#[cfg(feature = "logger-redaction")]
+fn parse_reads_the_redaction() {
+  let config = LoggerConfig::parse(
+    r#"
+      [[redaction.fields]]
+      name = "password"
+      masking = "partial"
+
+      [[redaction.patterns]]
+      pattern = "\\d{16}"
+    "#,
+    ConfigFormat::Toml,
+  )
+  .unwrap();
+
+  assert_eq!(config.redaction.fields[0].name, "password");
+  assert_eq!(config.redaction.fields[0].masking, Masking::Partial);
+  assert_eq!(config.redaction.patterns[0].masking, Masking::Full);
+}
+
+#[test]
+fn parse_accepts_the_sections_of_every_platform() {
+  let config = LoggerConfig::parse(
+    r#"
+      [syslog_logger]
+      enable = false
+
+      [journald_logger]
+      enable = false
+
+      [otel_exporter]
+      enable = false
+      protocol = "grpc"
+    "#,
+    ConfigFormat::Toml,
+  );
+
+  assert!(config.is_ok());
+}
+
+#[test]
+fn parse_names_the_unknown_key() {
+  let error = LoggerConfig::parse(
+    r#"{ "file_logger": { "enable": true, "rotation": "daily" } }"#,
+    ConfigFormat::Json,
+  )
+  .unwrap_err();
+
+  assert_eq!(error.category(), Some("CONFIG"));
+  assert_eq!(
+    error.message(),
+    "the config key 'file_logger.rotation' is not valid"
+  );
+}
+
+#[test]
+fn parse_names_the_key_with_a_wrong_value() {
+  let error = LoggerConfig::parse(
+    "[limiter]\nrate_limit = { count = 10, interval_ms = \"1s\" }\n",
+    ConfigFormat::Toml,
+  )
+  .unwrap_err();
+
+  assert_eq!(
+    error.message(),
+    "the config key 'limiter.rate_limit.interval_ms' is not valid"
+  );
+}
+
+#[test]
+fn parse_names_the_invalid_level() {
+  let error = LoggerConfig::parse("level: verbose\n", ConfigFormat::Yaml).unwrap_err();
+
+  let messages = get_error_messages(&error);
+  assert_eq!(messages[0], "the config key 'level' is not valid");
+  assert!(messages[1].contains("unknown variant `verbose`"));
+}
+
+#[test]
+fn parse_fails_with_invalid_syntax() {
+  let error = LoggerConfig::parse("level = ", ConfigFormat::Toml).unwrap_err();
+
+  assert_eq!(error.message(), "couldn't parse the TOML config");
+}
+
+#[test]
+fn from_file_needs_a_known_extension() {
+  let error = LoggerConfig::from_file("logger.ini").unwrap_err();
+
+  assert_eq!(
+    error.message(),
+    "the config file 'logger.ini' must have a 'toml', 'json' or 'yaml' extension"
+  );
+}
+
+#[test]
+fn from_config_applies_the_settings() {
+  let config = LoggerConfig::parse("level: warn", ConfigFormat::Yaml).unwrap();
+
+  let _capture = Logger::from_config(config).capture().unwrap();
+
+  tracing::info!("below the level");
+  tracing::warn!("retrying");
+
+  assert_eq!(commonlib::logger::testing::logs().len(), 1);
+  assert_logged!(message = "retrying");
+}
+
+#[test]
+#[cfg(feature = "logger-redaction")]
+fn from_config_applies_the_redaction() {
+  let config = LoggerConfig::parse(
+    r#"
+      redaction:
+        fields:
+          - name: token
+    "#,
+    ConfigFormat::Yaml,
+  )
+  .unwrap();
+
+  let _capture = Logger::from_config(config).capture().unwrap();
+
+  tracing::warn!("retrying with token=abcd");
+
+  assert_logged!(message = "retrying with token=[REDACTED]");
+}
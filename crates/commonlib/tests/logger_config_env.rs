@@ -0,0 +1,143 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+#![cfg(feature = "logger-config")]
+
+// The environment is shared by the whole process, so the tests take turns
+use std::{env, sync::Mutex};
+
+#[cfg(feature = "logger-redaction")]
+use commonlib::logger::Masking;
+use commonlib::logger::{ConfigFormat, LogLevel, LoggerConfig, Sampling};
+
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+fn with_env<T>(vars: &[(&str, &str)], f: impl FnOnce() -> T) -> T {
+  let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+  for (key, value) in vars {
+    env::set_var(key, value);
+  }
+
+  let result = f();
+
+  for (key, _) in vars {
+    env::remove_var(key);
+  }
+
+  result
+}
+
+fn get_config() -> LoggerConfig {
+  LoggerConfig::parse(
+    "level = \"info\"\n[file_logger]\nenable = false\npath = \"./logs\"\n",
+    ConfigFormat::Toml,
+  )
+  .unwrap()
+}
+
+#[test]
+fn env_overrides_the_config() {
+  let config = with_env(
+    &[
+      ("COMMONLIB_LOG_LEVEL", "DEBUG"),
+      ("COMMONLIB_LOG_FILE_LOGGER_ENABLE", "yes"),
+    ],
+    || get_config().with_env().unwrap(),
+  );
+
+  assert_eq!(config.level, Some(LogLevel::Debug));
+  assert_eq!(config.file_logger.enable, Some(true));
+  assert_eq!(config.file_logger.path.as_deref(), Some("./logs"));
+}
+
+#[test]
+fn env_sets_the_module_filters() {
+  let config = with_env(
+    &[(
+      "COMMONLIB_LOG_MODULE_FILTERS",
+      "my_crate::db=debug, my_crate::http=WARN",
+    )],
+    || get_config().with_env().unwrap(),
+  );
+
+  assert_eq!(config.module_filters["my_crate::db"], LogLevel::Debug);
+  assert_eq!(config.module_filters["my_crate::http"], LogLevel::Warn);
+}
+
+#[test]
+fn env_sets_the_nested_settings() {
+  let config = with_env(
+    &[
+      ("COMMONLIB_LOG_RING_BUFFER_CAPACITY", "25"),
+      ("COMMONLIB_LOG_CONSOLE_COMPACT_ERRORS", "true"),
+      (
+        "COMMONLIB_LOG_SAMPLER_LEVEL_SAMPLINGS",
+        r#"{ "debug": { "every_nth": 4 } }"#,
+      ),
+    ],
+    || get_config().with_env().unwrap(),
+  );
+
+  assert_eq!(config.ring_buffer.capacity, Some(25));
+  assert_eq!(config.console.compact_errors, Some(true));
+  assert_eq!(
+    config.sampler.level_samplings[&LogLevel::Debug],
+    Sampling::EveryNth(4)
+  );
+}
+
+#[test]
+#[cfg(feature = "logger-redaction")]
+fn env_sets_the_redaction() {
+  let config = with_env(
+    &[(
+      "COMMONLIB_LOG_REDACTION_FIELDS",
+      r#"[{ "name": "password", "masking": "hashed" }]"#,
+    )],
+    || get_config().with_env().unwrap(),
+  );
+
+  assert_eq!(config.redaction.fields[0].name, "password");
+  assert_eq!(config.redaction.fields[0].masking, Masking::Hashed);
+}
+
+#[test]
+fn env_names_the_invalid_variable() {
+  let error = with_env(&[("COMMONLIB_LOG_FILE_LOGGER_ENABLE", "maybe")], || {
+    get_config().with_env().unwrap_err()
+  });
+
+  assert_eq!(
+    error.message(),
+    "the environment variable 'COMMONLIB_LOG_FILE_LOGGER_ENABLE' is not valid"
+  );
+  assert_eq!(
+    error.chain().nth(1).unwrap().message(),
+    "the config key 'file_logger.enable' is not valid"
+  );
+}
+
+#[test]
+fn env_names_the_unknown_variable() {
+  let error = with_env(&[("COMMONLIB_LOG_FILE_LOGGER_ROTATION", "daily")], || {
+    get_config().with_env().unwrap_err()
+  });
+
+  assert_eq!(
+    error.chain().nth(1).unwrap().message(),
+    "the config key 'file_logger.rotation' is not valid"
+  );
+}
+
+#[test]
+fn env_needs_filters_like_module_level() {
+  let error = with_env(&[("COMMONLIB_LOG_MODULE_FILTERS", "my_crate::db")], || {
+    get_config().with_env().unwrap_err()
+  });
+
+  assert_eq!(
+    error.message(),
+    "the filter 'my_crate::db' of 'COMMONLIB_LOG_MODULE_FILTERS' must look like 'module=level'"
+  );
+}
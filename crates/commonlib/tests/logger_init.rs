@@ -0,0 +1,40 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+//! `Logger::init` installs process-global state, so it's tested in its own process.
+
+#![cfg(feature = "log-bridge")]
+
+use commonlib::{
+  assert_logged, assert_not_logged,
+  logger::{testing, Error, LogLevel, Logger},
+};
+
+#[test]
+fn init_bridges_the_log_crate_by_default() {
+  let logger = Logger::new().init().unwrap();
+
+  // The bridge forwards to the current subscriber, which is the capture
+  let _capture = Logger::new()
+    .set_level(LogLevel::Trace)
+    .add_module_filter("dependency::client", LogLevel::Warn)
+    .add_module_filter(module_path!(), LogLevel::Error)
+    .capture()
+    .unwrap();
+
+  log::error!(target: "dependency::client", "connection refused");
+  log::info!(target: "dependency::client", "connecting");
+  log::debug!(target: "dependency::server", "listening");
+  // Filtered by its target rather than by the module it's logged from
+  log::warn!(target: "dependency::client", "retrying");
+
+  assert_logged!(level = Error, message = "connection refused");
+  assert_not_logged!(message = "connecting");
+  assert_logged!(level = Debug, message = "listening");
+  assert_logged!(level = Warn, message = "retrying");
+  assert_eq!(testing::logs().len(), 3);
+
+  assert!(matches!(Logger::new().init(), Err(Error::Init(_))));
+
+  logger.stop();
+}
@@ -0,0 +1,30 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+//! `Logger::init` installs process-global state, so it's tested in its own process.
+
+#![cfg(feature = "log-bridge")]
+
+use commonlib::logger::{Error, Logger};
+
+struct NopLogger;
+
+impl log::Log for NopLogger {
+  fn enabled(&self, _: &log::Metadata) -> bool {
+    false
+  }
+
+  fn log(&self, _: &log::Record) {}
+
+  fn flush(&self) {}
+}
+
+#[test]
+fn init_installs_nothing_if_the_bridge_fails() {
+  log::set_logger(&NopLogger).unwrap();
+
+  let result = Logger::new().init();
+
+  assert!(matches!(result, Err(Error::LogBridge(_))));
+  assert!(!tracing::dispatcher::has_been_set());
+}
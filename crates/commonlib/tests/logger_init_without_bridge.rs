@@ -0,0 +1,27 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+//! `Logger::init` installs process-global state, so it's tested in its own process.
+
+#![cfg(feature = "log-bridge")]
+
+use commonlib::logger::Logger;
+
+struct NopLogger;
+
+impl log::Log for NopLogger {
+  fn enabled(&self, _: &log::Metadata) -> bool {
+    false
+  }
+
+  fn log(&self, _: &log::Record) {}
+
+  fn flush(&self) {}
+}
+
+#[test]
+fn init_without_the_bridge_leaves_the_log_crate_alone() {
+  Logger::new().setup_log_bridge(false).init().unwrap();
+
+  assert!(log::set_logger(&NopLogger).is_ok());
+}
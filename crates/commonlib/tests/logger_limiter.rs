@@ -0,0 +1,186 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+#![cfg(feature = "logger")]
+
+mod support;
+
+use std::{fs, path::Path, time::Duration};
+
+use commonlib::logger::{testing, LogLevel, Logger, RateLimit};
+use support::TempDir;
+
+fn read_logs(dir_path: &Path) -> String {
+  fs::read_dir(dir_path)
+    .unwrap()
+    .map(|e| fs::read_to_string(e.unwrap().path()).unwrap())
+    .collect()
+}
+
+fn get_messages() -> Vec<String> {
+  testing::logs()
+    .into_iter()
+    .map(|l| l.message.unwrap_or_default())
+    .collect()
+}
+
+fn get_logger(dir_path: &Path) -> Logger {
+  Logger::new()
+    .set_level(LogLevel::Info)
+    .setup_file_logger(true, Some(dir_path.to_string_lossy().to_string()))
+}
+
+#[test]
+fn dedup_collapses_the_repeated_logs() {
+  let _capture = Logger::new().set_dedup(true).capture().unwrap();
+
+  for message in ["retrying", "retrying", "retrying", "connected"] {
+    tracing::info!("{}", message);
+  }
+
+  assert_eq!(
+    get_messages(),
+    ["retrying", "last message repeated 2 times", "connected"]
+  );
+}
+
+#[test]
+fn dedup_keeps_the_callsites_apart() {
+  let _capture = Logger::new().set_dedup(true).capture().unwrap();
+
+  let poll = |message: &str| tracing::info!("{}", message);
+
+  for _ in 0..3 {
+    poll("polling the queue");
+    tracing::info!("waiting for jobs");
+  }
+
+  poll("polling stopped");
+
+  assert_eq!(
+    get_messages(),
+    [
+      "polling the queue",
+      "waiting for jobs",
+      "last message repeated 2 times",
+      "polling stopped"
+    ]
+  );
+}
+
+#[test]
+fn rate_limit_keeps_the_callsites_apart() {
+  let _capture = Logger::new()
+    .set_rate_limit(Some(RateLimit::new(2, Duration::from_secs(60))))
+    .capture()
+    .unwrap();
+
+  for i in 0..4 {
+    tracing::info!("first callsite {}", i);
+    tracing::info!("second callsite {}", i);
+  }
+
+  assert_eq!(
+    get_messages(),
+    [
+      "first callsite 0",
+      "second callsite 0",
+      "first callsite 1",
+      "second callsite 1"
+    ]
+  );
+}
+
+#[test]
+fn rate_limit_reports_the_dropped_logs_after_the_interval() {
+  let _capture = Logger::new()
+    .set_rate_limit(Some(RateLimit::new(1, Duration::from_millis(100))))
+    .capture()
+    .unwrap();
+
+  let line = line!() + 6;
+  for i in 0..4 {
+    if i == 3 {
+      std::thread::sleep(Duration::from_millis(150));
+    }
+
+    tracing::info!("request {}", i);
+  }
+
+  assert_eq!(
+    get_messages(),
+    [
+      "request 0".into(),
+      format!("2 messages from {}:{} were suppressed", file!(), line),
+      "request 3".into()
+    ]
+  );
+}
+
+#[test]
+fn stopping_reports_the_last_burst() {
+  let dir_path = TempDir::new("limiter-stop");
+
+  let logger = get_logger(&dir_path)
+    .set_dedup(true)
+    .set_rate_limit(Some(RateLimit::new(1, Duration::from_secs(60))))
+    .init_scoped()
+    .unwrap();
+
+  for i in 0..5 {
+    tracing::info!("request {}", i);
+  }
+
+  for _ in 0..3 {
+    tracing::warn!("disk almost full");
+  }
+
+  drop(logger);
+
+  let logs = read_logs(&dir_path);
+  assert!(logs.contains("request 0"));
+  assert!(!logs.contains("request 1"));
+  assert!(logs.contains("4 messages from ") && logs.contains(" were suppressed"));
+  assert!(logs.contains("last message repeated 2 times"));
+}
+
+#[test]
+fn summaries_are_reported_without_logging_again() {
+  let dir_path = TempDir::new("limiter-timer");
+
+  let _logger = get_logger(&dir_path).set_dedup(true).init_scoped().unwrap();
+
+  for _ in 0..3 {
+    tracing::warn!("disk almost full");
+  }
+
+  // The summary is reported in a round and written to the file in the next one
+  std::thread::sleep(Duration::from_millis(2500));
+
+  assert!(read_logs(&dir_path).contains("last message repeated 2 times"));
+}
+
+#[test]
+fn metrics_dont_count_the_summaries() {
+  let logger = Logger::new()
+    .set_dedup(true)
+    .set_rate_limit(Some(RateLimit::new(1, Duration::from_secs(60))))
+    .init_scoped()
+    .unwrap();
+
+  for _ in 0..3 {
+    tracing::warn!("disk almost full");
+  }
+
+  for i in 0..3 {
+    tracing::info!("request {}", i);
+  }
+
+  // Reports the repeated logs right away
+  logger.flush();
+
+  let metrics = logger.metrics();
+  assert_eq!(metrics.get_level(LogLevel::Warn), 1);
+  assert_eq!(metrics.get_level(LogLevel::Info), 1);
+  assert_eq!(metrics.dropped, 4);
+}
@@ -0,0 +1,34 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+//! `Logger::init` installs process-global state, so it's tested in its own process.
+
+#![cfg(feature = "log-bridge")]
+
+use std::time::Duration;
+
+use commonlib::logger::{testing, Logger, RateLimit};
+
+#[test]
+fn rate_limit_keeps_the_bridged_callsites_apart() {
+  let logger = Logger::new().init().unwrap();
+
+  // The bridge forwards to the current subscriber, which is the capture
+  let _capture = Logger::new()
+    .set_rate_limit(Some(RateLimit::new(1, Duration::from_secs(60))))
+    .capture()
+    .unwrap();
+
+  for i in 0..2 {
+    log::info!("first callsite {}", i);
+    log::info!("second callsite {}", i);
+  }
+
+  let messages = testing::logs()
+    .into_iter()
+    .map(|l| l.message.unwrap_or_default())
+    .collect::<Vec<_>>();
+  assert_eq!(messages, ["first callsite 0", "second callsite 0"]);
+
+  logger.stop();
+}
@@ -0,0 +1,76 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+#![cfg(feature = "otel")]
+
+use std::{
+  io::{BufRead, BufReader, Read, Write},
+  net::TcpListener,
+  thread,
+};
+
+use commonlib::logger::{LogLevel, Logger, OtelProtocol};
+use opentelemetry_proto::tonic::{
+  collector::logs::v1::ExportLogsServiceRequest,
+  common::v1::{any_value::Value, KeyValue},
+};
+use prost::Message;
+
+fn get_attribute<'a>(attributes: &'a [KeyValue], key: &str) -> Option<&'a str> {
+  attributes
+    .iter()
+    .find(|a| a.key == key)
+    .and_then(|a| match a.value.as_ref()?.value.as_ref()? {
+      Value::StringValue(value) => Some(value.as_str()),
+      _ => None,
+    })
+}
+
+#[test]
+fn exports_the_fields_of_the_event_as_attributes() {
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let address = listener.local_addr().unwrap();
+
+  let server = thread::spawn(move || {
+    let (mut stream, _) = listener.accept().unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+    let mut content_length = 0;
+    loop {
+      let mut line = String::new();
+      reader.read_line(&mut line).unwrap();
+      if let Some(length) = line.strip_prefix("Content-Length: ") {
+        content_length = length.trim().parse().unwrap();
+      }
+      if line == "\r\n" {
+        break;
+      }
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).unwrap();
+    stream.write_all(b"HTTP/1.1 200 OK\r\n\r\n").unwrap();
+
+    ExportLogsServiceRequest::decode(&body[..]).unwrap()
+  });
+
+  let logger = Logger::new()
+    .set_level(LogLevel::Info)
+    .setup_otel_exporter(
+      true,
+      Some(format!("http://{}", address)),
+      Some(OtelProtocol::HttpProtobuf),
+    )
+    .init_scoped()
+    .unwrap();
+
+  tracing::info!(category = "db", table = "users", rows = 3, "slow query");
+  drop(logger);
+
+  let request = server.join().unwrap();
+  let record = &request.resource_logs[0].scope_logs[0].log_records[0];
+  assert_eq!(get_attribute(&record.attributes, "category"), Some("DB"));
+  assert_eq!(get_attribute(&record.attributes, "table"), Some("users"));
+  assert_eq!(get_attribute(&record.attributes, "rows"), Some("3"));
+  assert_eq!(get_attribute(&record.attributes, "message"), None);
+}
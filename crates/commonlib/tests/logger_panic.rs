@@ -0,0 +1,42 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+#![cfg(feature = "logger")]
+
+mod support;
+
+// The panic hook is global, so the logger is only installed once in this process
+use std::{fs, time::Duration};
+
+use commonlib::logger::{LogLevel, Logger, RateLimit};
+use support::TempDir;
+
+#[test]
+fn panics_are_logged_past_the_limiter() {
+  let dir_path = TempDir::new("panic");
+
+  let logger = Logger::new()
+    .set_level(LogLevel::Info)
+    .setup_file_logger(true, Some(dir_path.to_string_lossy().to_string()))
+    .set_rate_limit(Some(RateLimit::new(1, Duration::from_secs(60))))
+    .setup_panic_hook(true, false)
+    .init()
+    .unwrap();
+
+  for i in 0..3 {
+    let _ = std::panic::catch_unwind(|| panic!("boom {}", i));
+  }
+
+  // The hook flushes the file logger, without waiting for its next round
+  let logs = fs::read_dir(&dir_path)
+    .unwrap()
+    .map(|e| fs::read_to_string(e.unwrap().path()).unwrap())
+    .collect::<String>();
+
+  for i in 0..3 {
+    assert!(logs.contains(&format!("boom {}", i)));
+  }
+
+  assert_eq!(logger.metrics().dropped, 0);
+  logger.stop();
+}
@@ -0,0 +1,88 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+#![cfg(feature = "logger")]
+
+mod support;
+
+use std::{fs, path::Path, time::Duration};
+
+use commonlib::logger::{LogLevel, Logger, RateLimit};
+use support::TempDir;
+use tracing::Level;
+
+fn get_logger(dump_path: &Path) -> Logger {
+  Logger::new().set_level(LogLevel::Info).setup_ring_buffer(
+    true,
+    3,
+    Some(dump_path.to_string_lossy().to_string()),
+  )
+}
+
+fn read_dumps(dump_path: &Path) -> Vec<String> {
+  let mut paths = fs::read_dir(dump_path)
+    .map(|d| d.map(|e| e.unwrap().path()).collect::<Vec<_>>())
+    .unwrap_or_default();
+  paths.sort();
+
+  paths
+    .into_iter()
+    .map(|p| fs::read_to_string(p).unwrap())
+    .collect()
+}
+
+#[test]
+fn nothing_below_the_level_is_enabled() {
+  let logger = Logger::new()
+    .set_level(LogLevel::Info)
+    .init_scoped()
+    .unwrap();
+
+  assert!(tracing::enabled!(Level::INFO));
+  assert!(!tracing::enabled!(Level::DEBUG));
+  drop(logger);
+
+  let _logger = get_logger(&TempDir::new("ring-enabled"))
+    .init_scoped()
+    .unwrap();
+
+  // Debug is enabled for the ring buffer, but not further down
+  assert!(tracing::enabled!(Level::DEBUG));
+  assert!(!tracing::enabled!(Level::TRACE));
+}
+
+#[test]
+fn errors_dump_the_last_logs() {
+  let dump_path = TempDir::new("ring-dump");
+
+  let _logger = get_logger(&dump_path).init_scoped().unwrap();
+
+  tracing::info!("too old to be kept");
+  tracing::debug!("below the level");
+  tracing::trace!("below the ring buffer level");
+  tracing::info!("about to fail");
+  tracing::error!("failed");
+
+  let dumps = read_dumps(&dump_path);
+  assert_eq!(dumps.len(), 1);
+  assert!(!dumps[0].contains("too old to be kept"));
+  assert!(dumps[0].contains("below the level"));
+  assert!(!dumps[0].contains("below the ring buffer level"));
+  assert!(dumps[0].contains("failed"));
+}
+
+#[test]
+fn dropped_errors_dont_dump() {
+  let dump_path = TempDir::new("ring-dropped");
+
+  let _logger = get_logger(&dump_path)
+    .set_rate_limit(Some(RateLimit::new(1, Duration::from_secs(60))))
+    .init_scoped()
+    .unwrap();
+
+  for i in 0..3 {
+    tracing::error!("failed {}", i);
+  }
+
+  assert_eq!(read_dumps(&dump_path).len(), 1);
+}
@@ -0,0 +1,104 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+#![cfg(feature = "logger")]
+
+mod support;
+
+use std::{fs, path::Path, time::Duration};
+
+use commonlib::logger::{LogLevel, Logger};
+use support::TempDir;
+
+fn read_logs(dir_path: &Path) -> String {
+  fs::read_dir(dir_path)
+    .unwrap()
+    .map(|e| fs::read_to_string(e.unwrap().path()).unwrap())
+    .collect()
+}
+
+fn get_logger(dir_path: &Path) -> Logger {
+  Logger::new()
+    .set_level(LogLevel::Info)
+    .setup_file_logger(true, Some(dir_path.to_string_lossy().to_string()))
+}
+
+#[test]
+fn dropping_the_scoped_logger_flushes_and_stops_the_sinks() {
+  let dir_path = TempDir::new("scoped-scoped");
+
+  let logger = get_logger(&dir_path).init_scoped().unwrap();
+  tracing::info!("before the drop");
+  drop(logger);
+
+  // Written by the last round, without waiting for the next one
+  let logs = read_logs(&dir_path);
+  assert!(logs.contains("before the drop"));
+
+  tracing::info!("after the drop");
+  std::thread::sleep(Duration::from_millis(1200));
+
+  assert_eq!(read_logs(&dir_path), logs);
+}
+
+#[test]
+fn flush_writes_the_buffered_logs_right_away() {
+  let dir_path = TempDir::new("scoped-flushed");
+
+  let logger = get_logger(&dir_path).init_scoped().unwrap();
+  tracing::info!("before the flush");
+  logger.flush();
+
+  assert!(read_logs(&dir_path).contains("before the flush"));
+}
+
+#[test]
+fn scoped_loggers_are_independent() {
+  let first_path = TempDir::new("scoped-first");
+  let second_path = TempDir::new("scoped-second");
+
+  let first = get_logger(&first_path).init_scoped().unwrap();
+  tracing::info!("to the first");
+  drop(first);
+
+  let second = get_logger(&second_path).init_scoped().unwrap();
+  tracing::info!("to the second");
+  drop(second);
+
+  assert!(read_logs(&first_path).contains("to the first"));
+  assert!(!read_logs(&first_path).contains("to the second"));
+  assert!(read_logs(&second_path).contains("to the second"));
+}
+
+#[test]
+#[cfg(feature = "log-bridge")]
+fn init_scoped_doesnt_install_the_log_bridge() {
+  struct NopLogger;
+
+  impl log::Log for NopLogger {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+      false
+    }
+
+    fn log(&self, _: &log::Record) {}
+
+    fn flush(&self) {}
+  }
+
+  let _logger = Logger::new().setup_log_bridge(true).init_scoped().unwrap();
+
+  assert!(log::set_logger(&NopLogger).is_ok());
+}
+
+#[test]
+fn stop_flushes_the_built_logger() {
+  let dir_path = TempDir::new("scoped-built");
+
+  let (logger, layer) = get_logger(&dir_path).build().unwrap();
+  let subscriber =
+    tracing_subscriber::layer::SubscriberExt::with(tracing_subscriber::registry(), layer);
+  tracing::subscriber::with_default(subscriber, || tracing::warn!("through the layer"));
+  logger.stop();
+
+  assert!(read_logs(&dir_path).contains("through the layer"));
+}
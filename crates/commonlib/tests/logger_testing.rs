@@ -0,0 +1,202 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+#![cfg(feature = "logger")]
+
+use commonlib::{
+  assert_logged, assert_not_logged,
+  logger::{
+    testing::{self, Matcher},
+    FileLog, LogLevel, Logger,
+  },
+};
+
+#[cfg(feature = "logger-redaction")]
+use commonlib::logger::Masking;
+
+fn get_log(level: LogLevel, category: Option<&str>, message: &str) -> FileLog {
+  FileLog {
+    timestamp: 0,
+    level,
+    category: category.map(|c| c.into()),
+    message: Some(message.into()),
+    stopwatch: None,
+    error: None,
+    sample_rate: None,
+  }
+}
+
+#[test]
+fn capture_collects_the_logs_of_every_level() {
+  let capture = testing::capture();
+
+  tracing::trace!("tracing the request");
+  tracing::error!(category = "db", "couldn't connect");
+
+  let logs = capture.logs();
+  assert_eq!(logs.len(), 2);
+  assert_eq!(logs[0].level, LogLevel::Trace);
+  assert_eq!(logs[1].category.as_deref(), Some("DB"));
+  assert_eq!(logs[1].message.as_deref(), Some("couldn't connect"));
+}
+
+#[test]
+fn capture_only_sees_the_current_thread() {
+  let capture = testing::capture();
+
+  std::thread::spawn(|| tracing::info!("from another thread"))
+    .join()
+    .unwrap();
+
+  assert!(capture.logs().is_empty());
+}
+
+#[test]
+fn capture_stops_when_dropped() {
+  let capture = testing::capture();
+  tracing::info!("captured");
+  let logs = capture.logs();
+  drop(capture);
+
+  tracing::info!("not captured");
+
+  assert_eq!(logs.len(), 1);
+}
+
+#[test]
+fn logs_are_the_ones_of_the_innermost_capture() {
+  let outer = testing::capture();
+  tracing::info!("outer");
+
+  {
+    let _inner = testing::capture();
+    tracing::info!("inner");
+
+    assert_eq!(testing::logs().len(), 1);
+    assert_logged!(message = "inner");
+    assert_not_logged!(message = "outer");
+  }
+
+  assert_logged!(message = "outer");
+  outer.clear();
+  assert!(testing::logs().is_empty());
+}
+
+#[test]
+fn logger_capture_applies_the_filters() {
+  let _capture = Logger::new()
+    .set_level(LogLevel::Info)
+    .add_module_filter("logger_testing", LogLevel::Warn)
+    .capture()
+    .unwrap();
+
+  tracing::debug!("below the level");
+  tracing::info!("below the module filter");
+  tracing::warn!("kept");
+
+  assert_eq!(testing::logs().len(), 1);
+  assert_logged!(level = Warn, message = "kept");
+}
+
+#[test]
+#[cfg(feature = "logger-redaction")]
+fn logger_capture_applies_the_redaction() {
+  let _capture = Logger::new()
+    .add_redacted_field("password", Masking::Full)
+    .capture()
+    .unwrap();
+
+  tracing::info!("login with password=hunter2");
+
+  assert_logged!(message = "login with password=[REDACTED]");
+}
+
+#[test]
+#[cfg(feature = "logger-redaction")]
+fn logger_capture_fails_with_an_invalid_redaction_pattern() {
+  let result = Logger::new()
+    .add_redaction_pattern("(unclosed", Masking::Full)
+    .capture();
+
+  assert!(result.is_err());
+}
+
+#[test]
+fn matcher_matches_the_level_and_fields() {
+  let log = get_log(LogLevel::Warn, Some("DB"), "retrying the query");
+
+  assert!(Matcher::new().matches(&log));
+  assert!(Matcher::new().level(LogLevel::Warn).matches(&log));
+  assert!(!Matcher::new().level(LogLevel::Error).matches(&log));
+  assert!(Matcher::new().field("category", false, "DB").matches(&log));
+  assert!(Matcher::new().field("cat", false, "DB").matches(&log));
+  assert!(!Matcher::new()
+    .field("message", false, "retry")
+    .matches(&log));
+  assert!(Matcher::new().field("msg", true, "retry").matches(&log));
+  assert!(!Matcher::new().field("error", true, "").matches(&log));
+}
+
+#[test]
+#[should_panic(expected = "the log field 'thread' is not valid")]
+fn matcher_rejects_an_unknown_field() {
+  let _ = Matcher::new().field("thread", true, "");
+}
+
+#[test]
+fn matcher_finds_the_first_match() {
+  let logs = [
+    get_log(LogLevel::Info, None, "first"),
+    get_log(LogLevel::Warn, None, "second"),
+    get_log(LogLevel::Warn, None, "third"),
+  ];
+
+  assert_eq!(Matcher::new().level(LogLevel::Warn).find(&logs), Some(1));
+  assert_eq!(Matcher::new().level(LogLevel::Error).find(&logs), None);
+}
+
+#[test]
+fn matcher_displays_its_conditions() {
+  let matcher = Matcher::new()
+    .level(LogLevel::Warn)
+    .field("category", false, "DB")
+    .field("message", true, "retry");
+
+  assert_eq!(
+    matcher.to_string(),
+    r#"level = Warn, category = "DB", message contains "retry""#
+  );
+}
+
+#[test]
+#[should_panic(expected = "no log matched 'level = Error'")]
+fn assert_logged_fails_without_a_match() {
+  let _capture = testing::capture();
+  tracing::warn!("not an error");
+
+  assert_logged!(level = Error);
+}
+
+#[test]
+#[should_panic(expected = "a log matched 'message contains \"secret\"'")]
+fn assert_not_logged_fails_with_a_match() {
+  let _capture = testing::capture();
+  tracing::info!("the secret is out");
+
+  assert_not_logged!(message contains "secret");
+}
+
+#[test]
+#[should_panic(expected = "the log field 'mesage' is not valid")]
+fn assert_not_logged_fails_with_a_misspelled_field() {
+  let _capture = testing::capture();
+  tracing::info!("the secret is out");
+
+  assert_not_logged!(mesage contains "x");
+}
+
+#[test]
+#[should_panic(expected = "there isn't any log capture in the current thread")]
+fn logs_fails_without_a_capture() {
+  testing::logs();
+}
@@ -0,0 +1,44 @@
+// Copyright (c) Toolbi Software. All rights reserved.
+// Check the README file in the project root for more information.
+
+//! Fixtures shared by the tests, the unit tests of the logger include it too.
+
+use std::{
+  fs,
+  ops::Deref,
+  path::{Path, PathBuf},
+};
+
+/// An empty directory under the temp dir that's removed on drop.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+  /// The name is made unique per process, so the tests can run in parallel.
+  pub fn new(name: &str) -> Self {
+    let path = std::env::temp_dir().join(format!("commonlib-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&path);
+    fs::create_dir_all(&path).unwrap();
+
+    Self(path)
+  }
+}
+
+impl Deref for TempDir {
+  type Target = Path;
+
+  fn deref(&self) -> &Path {
+    &self.0
+  }
+}
+
+impl AsRef<Path> for TempDir {
+  fn as_ref(&self) -> &Path {
+    &self.0
+  }
+}
+
+impl Drop for TempDir {
+  fn drop(&mut self) {
+    let _ = fs::remove_dir_all(&self.0);
+  }
+}
@@ -15,7 +15,7 @@ enum InputKind {
 pub fn error(input: TokenStream) -> TokenStream {
   let args = parse_macro_input!(input with Punctuated::<InputKind, Token![,]>::parse_terminated);
 
-  let message = (args.len() > 0)
+  let message = (!args.is_empty())
     .then(|| &args[0])
     .map(|a| as_variant!(a, InputKind::Lit).unwrap())
     .unwrap();
@@ -61,7 +61,7 @@ pub fn error(input: TokenStream) -> TokenStream {
 pub fn errorf(input: TokenStream) -> TokenStream {
   let args = parse_macro_input!(input with Punctuated::<InputKind, Token![,]>::parse_terminated);
 
-  let error = (args.len() > 0)
+  let error = (!args.is_empty())
     .then(|| &args[0])
     .map(|a| as_variant!(a, InputKind::Expr).unwrap())
     .unwrap();